@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::extract::{self, ExtractError, Upload};
+use crate::i18n;
+use crate::rag::{self, Chunk};
+use crate::state::{get_now, AppState};
+
+/// Metadata of an uploaded file. The extracted text lives next to the index
+/// in `attachments/<id>.txt`, its retrieval chunks in `<id>.chunks.json`;
+/// images are kept as-is in `<id>.img`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    pub id: String,
+    pub name: String,
+    pub mime: String,
+    pub hash: String,
+    pub size: u64,
+    pub chars: usize,
+    /// Page count of paginated documents (PDF); pages are separated by `\x0c` in the text.
+    #[serde(default)]
+    pub pages: Option<u32>,
+    /// The file was over the upload limit and only part of its text was kept.
+    #[serde(default)]
+    pub truncated: bool,
+    pub created_at: u64,
+}
+
+#[derive(Default)]
+pub struct AttachmentStore {
+    index: HashMap<String, Attachment>,
+    dir: Option<PathBuf>,
+    /// Chunks already read, so retrieval does not parse the chunk files again
+    /// for every chat message.
+    chunks: HashMap<String, Arc<Vec<Chunk>>>,
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl AttachmentStore {
+    pub fn load(dir: PathBuf) -> Self {
+        let index = fs::read_to_string(dir.join("index.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { index, dir: Some(dir), chunks: HashMap::new() }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Attachment> {
+        self.index.get(id)
+    }
+
+    pub fn find_by_hash(&self, hash: &str) -> Option<&Attachment> {
+        self.index.values().find(|a| a.hash == hash)
+    }
+
+    /// Stores the extracted text together with its retrieval chunks.
+    pub fn insert(&mut self, attachment: Attachment, text: &str) -> Result<(), String> {
+        let dir = self.dir.as_ref().ok_or("Attachment store is not initialized")?;
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        fs::write(dir.join(format!("{}.txt", attachment.id)), text).map_err(|e| e.to_string())?;
+        let chunks = rag::chunk_document(text);
+        let json = serde_json::to_string(&chunks).map_err(|e| e.to_string())?;
+        fs::write(dir.join(format!("{}.chunks.json", attachment.id)), json).map_err(|e| e.to_string())?;
+        self.chunks.insert(attachment.id.clone(), Arc::new(chunks));
+        self.index.insert(attachment.id.clone(), attachment);
+        self.save_index()
+    }
+
+    /// Stores an image unchanged; it has no text and no retrieval chunks.
+    pub fn insert_image(&mut self, attachment: Attachment, bytes: &[u8]) -> Result<(), String> {
+        let dir = self.dir.as_ref().ok_or("Attachment store is not initialized")?;
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        fs::write(dir.join(format!("{}.img", attachment.id)), bytes).map_err(|e| e.to_string())?;
+        self.index.insert(attachment.id.clone(), attachment);
+        self.save_index()
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<(), String> {
+        self.chunks.remove(id);
+        if self.index.remove(id).is_some() {
+            if let Some(dir) = &self.dir {
+                let _ = fs::remove_file(dir.join(format!("{}.txt", id)));
+                let _ = fs::remove_file(dir.join(format!("{}.chunks.json", id)));
+                let _ = fs::remove_file(dir.join(format!("{}.img", id)));
+            }
+        }
+        self.save_index()
+    }
+
+    pub fn read_text(&self, id: &str) -> Option<String> {
+        let dir = self.dir.as_ref()?;
+        fs::read_to_string(dir.join(format!("{}.txt", id))).ok()
+    }
+
+    pub fn read_image(&self, id: &str) -> Option<Vec<u8>> {
+        let dir = self.dir.as_ref()?;
+        fs::read(dir.join(format!("{}.img", id))).ok()
+    }
+
+    pub fn read_page(&self, id: &str, page: u32) -> Option<String> {
+        let index = page.checked_sub(1)? as usize;
+        self.read_text(id)?.split('\x0c').nth(index).map(|text| text.trim().to_string())
+    }
+
+    /// Retrieval chunks of an attachment, read from disk on first use; re-chunks
+    /// the text if the chunk file is missing.
+    pub fn read_chunks(&mut self, id: &str) -> Option<Arc<Vec<Chunk>>> {
+        if let Some(chunks) = self.chunks.get(id) {
+            return Some(chunks.clone());
+        }
+        let dir = self.dir.as_ref()?;
+        let chunks: Vec<Chunk> = fs::read_to_string(dir.join(format!("{}.chunks.json", id)))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .or_else(|| self.read_text(id).map(|text| rag::chunk_document(&text)))?;
+        let chunks = Arc::new(chunks);
+        self.chunks.insert(id.to_string(), chunks.clone());
+        Some(chunks)
+    }
+
+    fn save_index(&self) -> Result<(), String> {
+        let Some(dir) = &self.dir else { return Ok(()) };
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(&self.index).map_err(|e| e.to_string())?;
+        fs::write(dir.join("index.json"), json).map_err(|e| e.to_string())
+    }
+}
+
+/// Extracts the text of a file and stores it as an attachment named `name`.
+/// The format is sniffed from the content, see `extract::sniff`; text files
+/// over the size limit are truncated and images are stored unchanged. A file
+/// already stored (same hash) returns the existing attachment.
+pub fn import_file(state: &AppState, path: &Path, name: String, lang: &str) -> Result<Attachment, String> {
+    let (max_mb, strategy) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.max_upload_mb, settings.upload_truncation)
+    };
+    let localize = |e: ExtractError| match e {
+        ExtractError::Io(message) => i18n::tf(lang, "upload_read_error", &[&message]),
+        ExtractError::Binary => i18n::t(lang, "upload_binary"),
+        ExtractError::TooLarge { size, limit } => i18n::tf(lang, "upload_too_large", &[
+            &(size / (1024 * 1024)).to_string(),
+            &(limit / (1024 * 1024)).to_string(),
+        ]),
+        ExtractError::Unsupported(mime) => i18n::tf(lang, "upload_unsupported", &[&mime]),
+        ExtractError::Encrypted => i18n::t(lang, "upload_encrypted"),
+        ExtractError::NoText => i18n::t(lang, "upload_no_text"),
+        ExtractError::Failed { format, message } => i18n::tf(lang, "upload_extract_error", &[format, &message]),
+    };
+
+    let upload = extract::read_upload(path, max_mb * 1024 * 1024, strategy).map_err(localize)?;
+    let (hash, size, truncated) = match &upload {
+        Upload::Full(bytes) => (hash_bytes(bytes), bytes.len() as u64, false),
+        Upload::Truncated { head, tail, omitted } => {
+            let fingerprint = [head.as_slice(), tail.as_slice(), &omitted.to_le_bytes()[..]].concat();
+            (hash_bytes(&fingerprint), (head.len() + tail.len()) as u64 + omitted, true)
+        }
+    };
+    if let Some(existing) = state.attachments.lock().unwrap().find_by_hash(&hash) {
+        return Ok(existing.clone());
+    }
+
+    if let Upload::Full(bytes) = &upload {
+        let mime = extract::sniff(path, bytes);
+        if extract::is_image(mime) {
+            return store_image(state, name, mime.into(), hash, bytes);
+        }
+    }
+
+    let (mime, content) = state.extractors.extract_upload(path, &upload).map_err(localize)?;
+    store_text(state, name, mime, hash, size, truncated, &content)
+}
+
+/// Stores text that did not come from a file, such as the clipboard.
+pub fn import_text(state: &AppState, name: String, text: &str) -> Result<Attachment, String> {
+    let hash = hash_bytes(text.as_bytes());
+    if let Some(existing) = state.attachments.lock().unwrap().find_by_hash(&hash) {
+        return Ok(existing.clone());
+    }
+    store_text(state, name, extract::MIME_TEXT.into(), hash, text.len() as u64, false, text)
+}
+
+/// Stores image bytes that did not come from a file, such as a clipboard screenshot.
+pub fn import_image(state: &AppState, name: String, mime: &str, bytes: &[u8]) -> Result<Attachment, String> {
+    let hash = hash_bytes(bytes);
+    if let Some(existing) = state.attachments.lock().unwrap().find_by_hash(&hash) {
+        return Ok(existing.clone());
+    }
+    store_image(state, name, mime.into(), hash, bytes)
+}
+
+fn store_image(state: &AppState, name: String, mime: String, hash: String, bytes: &[u8]) -> Result<Attachment, String> {
+    let attachment = Attachment {
+        id: Uuid::new_v4().to_string(),
+        name,
+        mime,
+        hash,
+        size: bytes.len() as u64,
+        chars: 0,
+        pages: None,
+        truncated: false,
+        created_at: get_now(),
+    };
+    state.attachments.lock().unwrap().insert_image(attachment.clone(), bytes)?;
+    Ok(attachment)
+}
+
+fn store_text(state: &AppState, name: String, mime: String, hash: String, size: u64, truncated: bool, content: &str) -> Result<Attachment, String> {
+    let pages = (mime == extract::MIME_PDF).then(|| content.split('\x0c').count() as u32);
+    let attachment = Attachment {
+        id: Uuid::new_v4().to_string(),
+        name,
+        mime,
+        hash,
+        size,
+        chars: content.chars().count(),
+        pages,
+        truncated,
+        created_at: get_now(),
+    };
+    state.attachments.lock().unwrap().insert(attachment.clone(), content)?;
+    Ok(attachment)
+}
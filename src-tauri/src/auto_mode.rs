@@ -0,0 +1,214 @@
+use crate::personas::{Persona, BASIC_ID};
+use serde::{Deserialize, Serialize};
+
+/// Signals used by Auto mode on top of the personas' trigger keywords.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AutoModeRules {
+    #[serde(rename = "detectEntities", default = "default_true")]
+    pub detect_entities: bool,
+    #[serde(rename = "detectTimeSensitive", default = "default_true")]
+    pub detect_time_sensitive: bool,
+    #[serde(rename = "useModelClassifier", default)]
+    pub use_model_classifier: bool,
+    /// Keyword lists of older settings files; moved into the Search and
+    /// Philosophy personas on startup (`personas::migrate_legacy_keywords`).
+    #[serde(rename = "searchKeywords", default, skip_serializing)]
+    pub legacy_search_keywords: Vec<String>,
+    #[serde(rename = "philosophyKeywords", default, skip_serializing)]
+    pub legacy_philosophy_keywords: Vec<String>,
+}
+
+fn default_true() -> bool { true }
+
+impl Default for AutoModeRules {
+    fn default() -> Self {
+        Self {
+            detect_entities: true,
+            detect_time_sensitive: true,
+            use_model_classifier: false,
+            legacy_search_keywords: Vec::new(),
+            legacy_philosophy_keywords: Vec::new(),
+        }
+    }
+}
+
+pub struct Classification {
+    pub persona_id: String,
+    /// True when the rules found a clear winner; otherwise the caller may
+    /// fall back to the model-based classifier.
+    pub confident: bool,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Phrases match as substrings and single words as whole words. A keyword
+/// ending in `*` also matches as a word prefix, so suffixed Hungarian forms
+/// ("életről" for "élet*") still count.
+fn keyword_hits(content_words: &[String], lower: &str, keywords: &[String]) -> u32 {
+    keywords.iter()
+        .filter(|k| {
+            let k = k.trim().to_lowercase();
+            if k.contains(' ') {
+                lower.contains(&k)
+            } else if let Some(prefix) = k.strip_suffix('*').filter(|p| !p.is_empty()) {
+                content_words.iter().any(|w| w.starts_with(prefix))
+            } else {
+                content_words.contains(&k)
+            }
+        })
+        .count() as u32
+}
+
+/// Capitalised words that are not names of anything worth searching for.
+const NOT_ENTITIES: [&str; 16] = [
+    "i", "i'm", "i've", "i'll", "i'd", "mia", "ok", "okay", "hi", "hello", "thanks", "please",
+    "én", "szia", "kérlek", "köszi",
+];
+
+/// Capitalised words that do not start a sentence, e.g. "Budapest" or "Nvidia".
+fn named_entity_count(content: &str) -> u32 {
+    let mut count = 0;
+    let mut sentence_start = true;
+    for raw in content.split_whitespace() {
+        let word = raw.trim_matches(|c: char| !c.is_alphanumeric());
+        let is_entity = word.chars().next().is_some_and(|c| c.is_uppercase())
+            && word.chars().filter(|c| c.is_alphabetic()).count() >= 2
+            && !NOT_ENTITIES.contains(&word.to_lowercase().as_str());
+        if !sentence_start && is_entity {
+            count += 1;
+        }
+        sentence_start = raw.ends_with(['.', '!', '?']);
+    }
+    count
+}
+
+fn mentions_recent_year(content_words: &[String]) -> bool {
+    content_words.iter().any(|w| {
+        w.len() == 4 && w.parse::<u32>().is_ok_and(|y| (2020..=2100).contains(&y))
+    })
+}
+
+/// Scores every persona by its trigger keywords; personas with web search
+/// enabled also collect the time-sensitivity and named-entity signals.
+pub fn classify(content: &str, lang: Option<&str>, rules: &AutoModeRules, personas: &[Persona]) -> Classification {
+    let lower = content.to_lowercase();
+    let content_words = words(content);
+
+    let mut search_signal = 0;
+    if rules.detect_time_sensitive && mentions_recent_year(&content_words) {
+        search_signal += 2;
+    }
+    if rules.detect_entities {
+        search_signal += named_entity_count(content).min(2);
+    }
+
+    let mut scores: Vec<(u32, &Persona)> = personas.iter()
+        .map(|p| {
+            let mut score = keyword_hits(&content_words, &lower, &p.keywords_for(lang)) * 2;
+            if p.web_search {
+                score += search_signal;
+            }
+            (score, p)
+        })
+        .collect();
+    scores.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    let best = scores.first().map(|(score, _)| *score).unwrap_or(0);
+    let runner_up = scores.get(1).map(|(score, _)| *score).unwrap_or(0);
+
+    match scores.first() {
+        Some((score, persona)) if *score >= 2 && *score > runner_up => Classification {
+            persona_id: persona.id.clone(),
+            confident: true,
+        },
+        _ => Classification { persona_id: BASIC_ID.into(), confident: best == 0 },
+    }
+}
+
+/// Maps the single-word answer of the model classifier to a persona, by id or name.
+pub fn parse_model_label(label: &str, personas: &[Persona]) -> Option<String> {
+    let label = label.trim().to_lowercase();
+    personas.iter()
+        .find(|p| label.starts_with(&p.id.to_lowercase()) || label.starts_with(&p.name.to_lowercase()))
+        .map(|p| p.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::personas::{default_personas, PHILOSOPHY_ID, SEARCH_ID};
+
+    fn classify_en(content: &str, rules: &AutoModeRules) -> Classification {
+        classify(content, Some("en"), rules, &default_personas())
+    }
+
+    #[test]
+    fn keywords_pick_a_persona() {
+        let rules = AutoModeRules::default();
+        let search = classify_en("What are the latest news?", &rules);
+        assert_eq!((search.persona_id.as_str(), search.confident), (SEARCH_ID, true));
+        let philosophy = classify_en("Why do we fear death?", &rules);
+        assert_eq!((philosophy.persona_id.as_str(), philosophy.confident), (PHILOSOPHY_ID, true));
+    }
+
+    #[test]
+    fn no_signal_is_a_confident_basic() {
+        let result = classify_en("hello there", &AutoModeRules::default());
+        assert_eq!((result.persona_id.as_str(), result.confident), (BASIC_ID, true));
+    }
+
+    #[test]
+    fn tie_is_an_unsure_basic() {
+        let result = classify_en("why is life in the latest news", &AutoModeRules::default());
+        assert_eq!((result.persona_id.as_str(), result.confident), (BASIC_ID, false));
+    }
+
+    #[test]
+    fn hungarian_suffixes_and_language_filter() {
+        let personas = default_personas();
+        let rules = AutoModeRules::default();
+        let result = classify("Mi az igazság az életről?", Some("hu"), &rules, &personas);
+        assert_eq!(result.persona_id, PHILOSOPHY_ID);
+        // Hungarian keywords do not count in an English message.
+        let result = classify("igazság életről", Some("en"), &rules, &personas);
+        assert_eq!(result.persona_id, BASIC_ID);
+    }
+
+    #[test]
+    fn keywords_match_whole_words_unless_marked_as_prefix() {
+        let rules = AutoModeRules { detect_entities: false, ..AutoModeRules::default() };
+        assert_eq!(classify_en("what should my costume look like", &rules).persona_id, BASIC_ID);
+        assert_eq!(classify_en("fold the newspaper", &rules).persona_id, BASIC_ID);
+        assert_eq!(classify_en("how much does it cost", &rules).persona_id, SEARCH_ID);
+
+        let mut personas = default_personas();
+        let search = personas.iter_mut().find(|p| p.id == SEARCH_ID).unwrap();
+        search.trigger_keywords.insert("en".into(), vec!["news*".into()]);
+        assert_eq!(classify("fold the newspaper", Some("en"), &rules, &personas).persona_id, SEARCH_ID);
+    }
+
+    #[test]
+    fn year_and_entities_point_to_search() {
+        let rules = AutoModeRules::default();
+        assert_eq!(classify_en("tell me about 2026", &rules).persona_id, SEARCH_ID);
+        assert_eq!(classify_en("tell me about Nvidia and Intel", &rules).persona_id, SEARCH_ID);
+        // "I" and a sentence start are not entities.
+        assert_eq!(classify_en("Yes. I think so", &rules).persona_id, BASIC_ID);
+
+        let off = AutoModeRules { detect_time_sensitive: false, detect_entities: false, ..AutoModeRules::default() };
+        assert_eq!(classify_en("tell me about Nvidia in 2026", &off).persona_id, BASIC_ID);
+    }
+
+    #[test]
+    fn model_label_by_id_or_name() {
+        let personas = default_personas();
+        assert_eq!(parse_model_label(" Search.", &personas).as_deref(), Some(SEARCH_ID));
+        assert_eq!(parse_model_label("philosophy", &personas).as_deref(), Some(PHILOSOPHY_ID));
+        assert_eq!(parse_model_label("weather", &personas), None);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::attachments::{import_file, Attachment};
+use crate::i18n;
+use crate::state::AppState;
+use std::path::Path;
+use tauri::State;
+
+/// Stores a file as an attachment. The returned metadata's `id` is passed to
+/// `ask_mia` instead of the text itself.
+#[tauri::command]
+pub async fn upload_file(path: String, state: State<'_, AppState>) -> Result<Attachment, String> {
+    let lang = i18n::ui_language(&state);
+    let path_obj = Path::new(&path);
+    let file_name = path_obj.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    let attachment = import_file(&state, path_obj, file_name, &lang)?;
+    println!(">>> Csatolmány mentve: {} ({} karakter)", attachment.name, attachment.chars);
+    Ok(attachment)
+}
+
+#[tauri::command]
+pub async fn get_attachments(ids: Vec<String>, state: State<'_, AppState>) -> Result<Vec<Attachment>, String> {
+    let store = state.attachments.lock().unwrap();
+    Ok(ids.iter().filter_map(|id| store.get(id).cloned()).collect())
+}
+
+/// The text of one page (1-based) of a paginated attachment such as a PDF.
+#[tauri::command]
+pub async fn get_attachment_page(id: String, page: u32, state: State<'_, AppState>) -> Result<String, String> {
+    let lang = i18n::ui_language(&state);
+    state.attachments.lock().unwrap()
+        .read_page(&id, page)
+        .ok_or_else(|| i18n::tf(&lang, "attachment_page_not_found", &[&page.to_string()]))
+}
+
+#[tauri::command]
+pub async fn delete_attachment(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.attachments.lock().unwrap().remove(&id)
+}
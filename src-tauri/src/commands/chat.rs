@@ -1,22 +1,27 @@
-use crate::state::{AppState, MiaModel, ChatMessage, MiaMode, WebSource};
+use crate::rag::{self, tokenize_for_match, Chunk, Citation};
+use crate::extract;
+use crate::model_manager;
+use crate::auto_mode;
+use crate::grammar;
+use crate::i18n;
+use crate::personas::{self, Mirostat, Persona, SamplingParams};
+use crate::commands::search::fetch_web_results;
+use crate::state::{get_now, AppState, Chat, DraftModel, MiaModel, ChatMessage, MiaMode, WebSource};
 use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::context::LlamaContext;
 use llama_cpp_2::llama_batch::LlamaBatch;
-use llama_cpp_2::model::params::LlamaModelParams;
 use llama_cpp_2::model::{AddBos, LlamaModel};
+use llama_cpp_2::mtmd::{mtmd_default_marker, MtmdBitmap, MtmdInputText};
 use llama_cpp_2::sampling::LlamaSampler;
+use llama_cpp_2::token::LlamaToken;
 use std::num::NonZeroU32;
-use std::path::PathBuf;
-use tauri::{Manager, State, Emitter};
+use tauri::{Manager, State};
 use serde::Serialize;
 use uuid::Uuid;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use scraper::{Html, Selector};
-use std::path::Path;
-use pdf_extract::extract_text;
-use dotext::*;
-use std::io::Read;
+use std::sync::Arc;
 
 #[derive(Serialize)]
 pub struct MiaResponse {
@@ -24,6 +29,11 @@ pub struct MiaResponse {
     pub tokens: i32,
     pub speed: f32,
     pub sources: Vec<WebSource>,
+    pub citations: Vec<Citation>,
+    pub mode: MiaMode,
+    /// Share of drafted tokens the main model kept, with speculative decoding.
+    #[serde(rename = "acceptanceRate")]
+    pub acceptance_rate: Option<f32>,
 }
 
 #[derive(Serialize)]
@@ -31,24 +41,23 @@ pub struct ChatEntry {
     pub id: String,
     pub name: String,
     pub last_active: u64,
+    /// The chat's own model (`set_chat_model`), if any.
+    pub model: Option<String>,
 }
 
-fn get_now() -> u64 {
-    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
-}
-
-fn tokenize_for_match(text: &str) -> Vec<String> {
-    text.split(|c: char| !c.is_alphanumeric())
-        .filter_map(|w| {
-            let w = w.to_lowercase();
-            if w.len() >= 3 {
-                Some(w)
-            } else {
-                None
-            }
-        })
-        .collect()
-}
+/// Characters of document excerpts retrieved for a question; `fit_prompt`
+/// shortens them further when the prompt would not fit the context.
+const DOCUMENT_CONTEXT_BUDGET: usize = 3000;
+/// Context window of a text generation, in tokens, unless the model was
+/// trained on less.
+const CONTEXT_TOKENS: u32 = 4096;
+/// Context added for image prompts, whose embeddings take hundreds of tokens each.
+const IMAGE_CONTEXT_TOKENS: u32 = 4096;
+/// Longest reply of `ask_mia`; the prompt is trimmed to leave room for it.
+const REPLY_TOKENS: usize = 512;
+/// Images from the recent messages put in one prompt; each takes hundreds of
+/// tokens of the vision context.
+const MAX_PROMPT_IMAGES: usize = 3;
 
 fn build_memory_context(history: &[ChatMessage], current_user_message: &str) -> (String, String) {
     if history.is_empty() {
@@ -96,7 +105,7 @@ fn build_memory_context(history: &[ChatMessage], current_user_message: &str) ->
             .filter(|(score, _)| *score > 0)
             .collect();
 
-        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
         scored.truncate(3);
 
         if !scored.is_empty() {
@@ -110,7 +119,148 @@ fn build_memory_context(history: &[ChatMessage], current_user_message: &str) ->
     (summary, rag)
 }
 
-fn save_chats_to_disk(handle: &tauri::AppHandle, chats: &HashMap<String, Vec<ChatMessage>>) -> Result<(), String> {
+/// The images attached to the recent messages, oldest first, at most
+/// `MAX_PROMPT_IMAGES` of the newest ones, and how many belong to each message.
+fn prompt_images(state: &AppState, history: &[ChatMessage]) -> (Vec<Vec<u8>>, Vec<usize>) {
+    let store = state.attachments.lock().unwrap();
+    let mut images = Vec::new();
+    let mut counts = vec![0; history.len()];
+    for (i, msg) in history.iter().enumerate().rev() {
+        for id in msg.attachments.iter().flatten().rev() {
+            if images.len() == MAX_PROMPT_IMAGES {
+                break;
+            }
+            if store.get(id).is_some_and(|a| extract::is_image(&a.mime)) {
+                if let Some(bytes) = store.read_image(id) {
+                    images.push(bytes);
+                    counts[i] += 1;
+                }
+            }
+        }
+    }
+    images.reverse();
+    (images, counts)
+}
+
+/// Retrieves the chunks of the chat's attached documents and knowledge bases
+/// that best match the question, labelled so the model can cite document and
+/// page/section.
+fn build_document_context(state: &AppState, chat: &Chat, question: &str) -> (String, Vec<Citation>) {
+    let mut ids: Vec<String> = Vec::new();
+    for msg in chat.messages.iter().rev() {
+        for id in msg.attachments.iter().flatten() {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+    }
+    {
+        let knowledge = state.knowledge.lock().unwrap();
+        for base in chat.knowledge_bases.iter().filter_map(|id| knowledge.get(id)) {
+            for id in base.attachment_ids() {
+                if !ids.contains(id) {
+                    ids.push(id.clone());
+                }
+            }
+        }
+    }
+    if ids.is_empty() {
+        return (String::new(), Vec::new());
+    }
+
+    let mut store = state.attachments.lock().unwrap();
+    let loaded: Vec<(String, Arc<Vec<Chunk>>)> = ids.iter()
+        .filter_map(|id| Some((store.get(id)?.name.clone(), store.read_chunks(id)?)))
+        .collect();
+    drop(store);
+
+    let documents: Vec<(&str, &[Chunk])> = loaded.iter().map(|(name, chunks)| (name.as_str(), chunks.as_slice())).collect();
+    let mut hits = rag::search(&documents, question, 8);
+    if hits.is_empty() {
+        // Nothing matched the question (e.g. "summarize this"): use each document's opening
+        hits = documents.iter()
+            .filter_map(|&(document, chunks)| chunks.first().map(|chunk| rag::Hit { document, chunk, score: 0.0 }))
+            .collect();
+    }
+
+    let mut used = 0usize;
+    hits.retain(|hit| {
+        used += hit.chunk.text.chars().count();
+        used <= DOCUMENT_CONTEXT_BUDGET
+    });
+    hits.sort_by(|a, b| a.document.cmp(b.document).then(a.chunk.index.cmp(&b.chunk.index)));
+
+    let mut context = String::from(
+        "Excerpts from the user's attached documents. When you use one, cite it in parentheses \
+         exactly as labelled, e.g. (report.pdf, p. 3).\n"
+    );
+    let mut citations: Vec<Citation> = Vec::new();
+    for hit in &hits {
+        let citation = hit.citation();
+        context.push_str(&format!("\n[{}]\n{}\n", citation.label(), hit.chunk.text.trim()));
+        if !citations.iter().any(|c| c.label() == citation.label()) {
+            citations.push(citation);
+        }
+    }
+    (context, citations)
+}
+
+fn render_prompt(system: &str, contexts: &[String], history: &[ChatMessage], image_counts: &[usize]) -> String {
+    let mut system_block = system.to_string();
+    for context in contexts.iter().filter(|c| !c.is_empty()) {
+        system_block.push_str("\n\n");
+        system_block.push_str(context);
+    }
+    let mut prompt = format!("<|im_start|>system\n{}<|im_end|>\n", system_block);
+    for (msg, &count) in history.iter().zip(image_counts) {
+        let media = mtmd_default_marker().repeat(count);
+        prompt.push_str(&format!("<|im_start|>{}\n{}{}<|im_end|>\n", msg.role, media, msg.content));
+    }
+    prompt.push_str("<|im_start|>assistant\n");
+    prompt
+}
+
+/// Builds the `ask_mia` prompt within `budget` tokens. `contexts` are the web
+/// results, document excerpts, summary and relevant older messages. Over
+/// budget, the oldest history messages go first (with their images), then the
+/// older messages and the summary, then the longer of the web and document
+/// excerpts is halved until it fits. The question itself is always kept; when
+/// it alone is too long, generation reports it.
+fn fit_prompt(
+    model: &LlamaModel,
+    budget: usize,
+    system: &str,
+    mut contexts: [String; 4],
+    mut history: Vec<ChatMessage>,
+    mut image_counts: Vec<usize>,
+    images: &mut Vec<Vec<u8>>,
+) -> Result<String, String> {
+    let mut trimmed = false;
+    loop {
+        let prompt = render_prompt(system, &contexts, &history, &image_counts);
+        let used = model.str_to_token(&prompt, AddBos::Never).map_err(|e| e.to_string())?.len();
+        if used <= budget {
+            if trimmed {
+                println!(">>> Prompt rövidítve a kontextushoz: {} token, {} üzenet", used, history.len());
+            }
+            return Ok(prompt);
+        }
+        trimmed = true;
+        if history.len() > 1 {
+            history.remove(0);
+            images.drain(..image_counts.remove(0));
+        } else if let Some(older) = contexts[2..].iter_mut().rev().find(|c| !c.is_empty()) {
+            older.clear();
+        } else if let Some(excerpts) = contexts[..2].iter_mut().filter(|c| !c.is_empty()).max_by_key(|c| c.len()) {
+            let half = excerpts.char_indices().nth(excerpts.chars().count() / 2).map_or(0, |(i, _)| i);
+            excerpts.truncate(half);
+        } else {
+            return Ok(prompt);
+        }
+    }
+}
+
+pub(crate) fn save_chats_to_disk(handle: &tauri::AppHandle, chats: &HashMap<String, Chat>) -> Result<(), String> {
     let app_dir = handle.path().app_data_dir().map_err(|e| e.to_string())?;
     if !app_dir.exists() {
         fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
@@ -121,111 +271,403 @@ fn save_chats_to_disk(handle: &tauri::AppHandle, chats: &HashMap<String, Vec<Cha
     Ok(())
 }
 
-async fn fetch_web_results(query: &str) -> (String, Vec<WebSource>) {
-    let mut sources = Vec::new();
-    let client = match reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-        .build() {
-            Ok(c) => c,
-            Err(_) => return ("Search failed to initialize.".into(), Vec::new()),
-        };
+/// Looks up the persona for a concrete mode, falling back to Basic when it was deleted.
+fn persona_for_mode(state: &AppState, mode: &MiaMode) -> Persona {
+    let list = state.personas.lock().unwrap();
+    let id = mode.persona_id().unwrap_or_else(|| personas::BASIC_ID.into());
+    personas::find(&list, &id)
+        .or_else(|| personas::find(&list, personas::BASIC_ID))
+        .cloned()
+        .unwrap_or_else(|| personas::default_personas().remove(0))
+}
 
-    let url = format!("https://html.duckduckgo.com/html/?q={}", query);
-    let res = match client.get(url).send().await {
-        Ok(r) => r,
-        Err(_) => return ("Failed to connect to search engine.".into(), Vec::new()),
-    };
+/// The model a chat answers with: its own choice, then the persona's, then the default.
+fn model_for(chat_model: Option<&str>, persona: &Persona) -> String {
+    chat_model
+        .or(persona.model.as_deref())
+        .unwrap_or(model_manager::DEFAULT_MODEL)
+        .to_string()
+}
+
+/// The model of a chat (the active one when `chat_id` is omitted), picked as
+/// in `ask_mia`. Auto mode picks its persona per message, so it counts as
+/// Basic here.
+pub(crate) fn chat_model(state: &AppState, chat_id: Option<&str>) -> String {
+    let chat_id = chat_id.map_or_else(|| state.active_chat_id.lock().unwrap().clone(), str::to_string);
+    let (mode, chat_model) = state.chats.lock().unwrap()
+        .get(&chat_id)
+        .map(|chat| (chat.mode.clone(), chat.model.clone()))
+        .unwrap_or_default();
+    model_for(chat_model.as_deref(), &persona_for_mode(state, &mode))
+}
+
+/// The model of the active chat, to preload when the window opens.
+pub(crate) fn active_chat_model(state: &AppState) -> String {
+    chat_model(state, None)
+}
+
+/// Checks that `model` names a file in `models/`.
+pub(crate) fn validate_model(state: &AppState, model: Option<&str>) -> Result<(), String> {
+    match model {
+        Some(name) if !model_manager::available_models().iter().any(|m| m == name) => {
+            Err(i18n::tf(&i18n::ui_language(state), "model_not_found", &[name]))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(crate) struct Generation {
+    pub text: String,
+    pub tokens: i32,
+    pub elapsed: Duration,
+    /// Speculative decoding only: tokens proposed by the draft model and kept.
+    pub drafted: usize,
+    pub accepted: usize,
+}
 
-    let html_content = res.text().await.unwrap_or_default();
-    let document = Html::parse_document(&html_content);
-    let result_selector = Selector::parse(".result__body").unwrap();
-    let title_selector = Selector::parse(".result__a").unwrap();
-    let snippet_selector = Selector::parse(".result__snippet").unwrap();
-
-    let mut search_context = String::from("\nWeb Search Data (Current Date: 2026):\n");
-
-    for result in document.select(&result_selector).take(5) {
-        let title_el = result.select(&title_selector).next();
-        let snippet_el = result.select(&snippet_selector).next();
-
-        if let (Some(t_el), Some(s_el)) = (title_el, snippet_el) {
-            let title = t_el.text().collect::<String>();
-            let snippet = s_el.text().collect::<String>();
-            let raw_url = t_el.value().attr("href").unwrap_or_default();
-
-            if !title.is_empty() && !raw_url.is_empty() {
-                let clean_url = if raw_url.starts_with("//") { 
-                    format!("https:{}", raw_url) 
-                } else if raw_url.contains("http") {
-                    raw_url.to_string()
-                } else {
-                    format!("https://duckduckgo.com{}", raw_url)
-                };
-
-                sources.push(WebSource { 
-                    title: title.clone(), 
-                    url: clean_url.clone() 
-                });
-                
-                search_context.push_str(&format!("- Source: {}\n  Content: {}\n\n", title, snippet));
+/// Sequences DRY does not extend a repetition across, as in llama.cpp.
+const DRY_SEQUENCE_BREAKERS: [&str; 4] = ["\n", ":", "\"", "*"];
+
+/// Penalties first, then temperature and either mirostat or the truncation
+/// samplers, ending in a draw seeded by `seed` (random when unset).
+pub(crate) fn build_sampler(model: &LlamaModel, params: &SamplingParams) -> LlamaSampler {
+    let seed = params.seed.unwrap_or_else(rand::random);
+    let mut chain = Vec::new();
+    if params.repeat_penalty != 1.0 || params.frequency_penalty != 0.0 || params.presence_penalty != 0.0 {
+        chain.push(LlamaSampler::penalties(
+            params.penalty_last_n,
+            params.repeat_penalty,
+            params.frequency_penalty,
+            params.presence_penalty,
+        ));
+    }
+    if params.dry_multiplier > 0.0 {
+        chain.push(LlamaSampler::dry(
+            model,
+            params.dry_multiplier,
+            params.dry_base,
+            params.dry_allowed_length,
+            params.dry_last_n,
+            DRY_SEQUENCE_BREAKERS,
+        ));
+    }
+    chain.push(LlamaSampler::temp(params.temperature));
+    match params.mirostat {
+        Mirostat::V1 => chain.push(LlamaSampler::mirostat(model.n_vocab(), seed, params.mirostat_tau, params.mirostat_eta, 100)),
+        Mirostat::V2 => chain.push(LlamaSampler::mirostat_v2(seed, params.mirostat_tau, params.mirostat_eta)),
+        Mirostat::Off => {
+            chain.push(LlamaSampler::top_k(params.top_k));
+            if params.typical_p < 1.0 {
+                chain.push(LlamaSampler::typical(params.typical_p, 1));
             }
+            chain.push(LlamaSampler::top_p(params.top_p, 1));
+            chain.push(LlamaSampler::min_p(params.min_p, 1));
+            chain.push(LlamaSampler::dist(seed));
         }
     }
+    LlamaSampler::chain(chain, false)
+}
 
-    if sources.is_empty() {
-        (String::from("No search results found on the web."), Vec::new())
-    } else {
-        (search_context, sources)
+/// `build_sampler` behind llama.cpp's grammar sampler, so only text the GBNF
+/// `grammar` (with a `root` rule) accepts can be generated.
+pub(crate) fn constrained_sampler(model: &LlamaModel, grammar: &str, params: &SamplingParams) -> Result<LlamaSampler, String> {
+    let grammar = LlamaSampler::grammar(model, grammar, "root").map_err(|e| e.to_string())?;
+    Ok(LlamaSampler::chain(vec![grammar, build_sampler(model, params)], false))
+}
+
+fn context_params(brain: &MiaModel, n_ctx: u32) -> LlamaContextParams {
+    LlamaContextParams::default()
+        .with_n_ctx(NonZeroU32::new(n_ctx))
+        .with_n_batch(n_ctx)
+        .with_n_threads(brain.threads)
+        .with_n_threads_batch(brain.threads)
+}
+
+/// Context window of a text generation with this model.
+pub(crate) fn context_size(brain: &MiaModel) -> u32 {
+    match brain.model.n_ctx_train() {
+        0 => CONTEXT_TOKENS,
+        trained => CONTEXT_TOKENS.min(trained),
     }
 }
 
-fn get_settings_for_mode(content: &str, current_mode: &MiaMode) -> (String, f32) {
-    let mut final_mode = current_mode.clone();
+/// Tokenizes the prompt, failing when it leaves less than `max_tokens` of the
+/// `n_ctx` context for the reply.
+fn prompt_tokens(brain: &MiaModel, prompt: &str, n_ctx: u32, max_tokens: usize) -> Result<Vec<LlamaToken>, String> {
+    let tokens = brain.model.str_to_token(prompt, AddBos::Never).map_err(|e| e.to_string())?;
+    if tokens.len() + max_tokens > n_ctx as usize {
+        return Err(format!(
+            "The prompt is {} tokens long; with {} for the reply it does not fit the context of {} tokens",
+            tokens.len(), max_tokens, n_ctx
+        ));
+    }
+    Ok(tokens)
+}
 
-    if final_mode == MiaMode::Auto {
-        let philo_keywords = vec!["miért", "élet", "halál", "értelem", "világ", "létezés", "igazság", "filozófia"];
-        let lower_content = content.to_lowercase();
-        if philo_keywords.iter().any(|&k| lower_content.contains(k)) {
-            final_mode = MiaMode::Philosophy;
-        }
+/// The whole prompt in one batch, with logits for its last token only.
+fn prompt_batch(tokens: &[LlamaToken]) -> Result<LlamaBatch, String> {
+    let mut batch = LlamaBatch::new(tokens.len().max(1), 1);
+    for (i, token) in tokens.iter().enumerate() {
+        batch.add(*token, i as i32, &[0], i == tokens.len() - 1).map_err(|e| e.to_string())?;
+    }
+    Ok(batch)
+}
+
+/// Runs the prompt through the model. `elapsed` covers token generation only,
+/// not the prompt decode.
+pub(crate) fn generate_text(
+    brain: &MiaModel,
+    prompt: &str,
+    sampler: &mut LlamaSampler,
+    max_tokens: usize,
+) -> Result<Generation, String> {
+    let n_ctx = context_size(brain);
+    let tokens = prompt_tokens(brain, prompt, n_ctx, max_tokens)?;
+    let mut ctx = brain.model.new_context(&brain.backend, context_params(brain, n_ctx)).map_err(|e| e.to_string())?;
+
+    let mut batch = prompt_batch(&tokens)?;
+    ctx.decode(&mut batch).map_err(|e| e.to_string())?;
+
+    continue_generation(brain, &mut ctx, sampler, tokens.len() as i32, batch.n_tokens() - 1, max_tokens)
+}
+
+/// Like `generate_text`, with images in place of the media markers of the
+/// prompt (see `mtmd_default_marker`). Needs the vision projector.
+pub(crate) fn generate_with_images(
+    brain: &MiaModel,
+    prompt: &str,
+    images: &[Vec<u8>],
+    sampler: &mut LlamaSampler,
+    max_tokens: usize,
+) -> Result<Generation, String> {
+    let vision = brain.vision.as_ref().ok_or("Vision projector not loaded")?;
+    let ctx_params = context_params(brain, context_size(brain) + IMAGE_CONTEXT_TOKENS).with_n_batch(512);
+    let mut ctx = brain.model.new_context(&brain.backend, ctx_params).map_err(|e| e.to_string())?;
+
+    let bitmaps = images.iter()
+        .map(|bytes| MtmdBitmap::from_buffer(vision, bytes))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let text = MtmdInputText { text: prompt.to_string(), add_special: false, parse_special: true };
+    let chunks = vision.tokenize(text, &bitmaps.iter().collect::<Vec<_>>()).map_err(|e| e.to_string())?;
+    let n_past = chunks.eval_chunks(vision, &ctx, 0, 0, 512, true).map_err(|e| e.to_string())?;
+
+    continue_generation(brain, &mut ctx, sampler, n_past, -1, max_tokens)
+}
+
+/// Samples up to `max_tokens` after a decoded prompt of `n_past` tokens whose
+/// logits are at `logits_index`.
+fn continue_generation(
+    brain: &MiaModel,
+    ctx: &mut LlamaContext,
+    sampler: &mut LlamaSampler,
+    n_past: i32,
+    logits_index: i32,
+    max_tokens: usize,
+) -> Result<Generation, String> {
+    let start_time = Instant::now();
+    let mut generated_tokens = 0;
+    let mut response_text = String::new();
+    let mut decoder = encoding_rs::UTF_8.new_decoder();
+    let mut batch = LlamaBatch::new(1, 1);
+    let mut token = sampler.sample(ctx, logits_index);
+
+    for n_cur in n_past..n_past + max_tokens as i32 {
+        if brain.model.is_eog_token(token) { break; }
+        let piece = brain.model.token_to_piece(token, &mut decoder, false, None).map_err(|e| e.to_string())?;
+        response_text.push_str(&piece);
+
+        batch.clear();
+        batch.add(token, n_cur, &[0], true).map_err(|e| e.to_string())?;
+        ctx.decode(&mut batch).map_err(|e| e.to_string())?;
+        token = sampler.sample(ctx, 0);
+        generated_tokens += 1;
     }
 
-    match final_mode {
-        MiaMode::Philosophy => (
-            "You are Mia, in Philosopher Mode. Provide deep existential insights. Use poetic, serious language and challenge the user's perspective.".to_string(),
-            1.25
-        ),
-        MiaMode::Search => (
-            "You are Mia, a Fact-Checking Assistant. Answer using the provided web context accurately. \
-             DO NOT include URLs or links in your response text. Provide ONLY the information. \
-             The sources will be displayed as separate buttons by the system.".to_string(),
-            0.3
-        ),
-        _ => (
-            "You are Mia, a cute and smart AI assistant. Your goal is to be helpful and kind. Use a friendly tone and emojis.".to_string(),
-            0.75
-        ),
+    Ok(Generation { text: response_text, tokens: generated_tokens, elapsed: start_time.elapsed(), drafted: 0, accepted: 0 })
+}
+
+fn push_piece(model: &LlamaModel, token: LlamaToken, decoder: &mut encoding_rs::Decoder, text: &mut String) -> Result<(), String> {
+    text.push_str(&model.token_to_piece(token, decoder, false, None).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+/// Speculative decoding: the draft model greedily proposes a few tokens, the
+/// main model checks them in one batch and keeps the prefix its own sampler
+/// agrees with, plus the token it sampled where they diverged. The text is
+/// what `generate_text` would produce; it only arrives faster when the draft
+/// guesses well.
+pub(crate) fn generate_speculative(
+    brain: &MiaModel,
+    draft: &DraftModel,
+    prompt: &str,
+    sampler: &mut LlamaSampler,
+    max_tokens: usize,
+) -> Result<Generation, String> {
+    let n_ctx = context_size(brain);
+    let tokens = prompt_tokens(brain, prompt, n_ctx, max_tokens)?;
+    let mut ctx = brain.model.new_context(&brain.backend, context_params(brain, n_ctx)).map_err(|e| e.to_string())?;
+    let mut draft_ctx = draft.model.new_context(&brain.backend, context_params(brain, n_ctx)).map_err(|e| e.to_string())?;
+
+    let mut batch = prompt_batch(&tokens)?;
+    ctx.decode(&mut batch).map_err(|e| e.to_string())?;
+    draft_ctx.decode(&mut batch).map_err(|e| e.to_string())?;
+
+    let start_time = Instant::now();
+    let mut draft_sampler = LlamaSampler::greedy();
+    let mut decoder = encoding_rs::UTF_8.new_decoder();
+    let mut text = String::new();
+    let mut generated = 0usize;
+    let (mut drafted, mut accepted) = (0usize, 0usize);
+    let mut step = LlamaBatch::new(draft.max_tokens + 1, 1);
+    // Position of `last`, which is sampled but not yet decoded by either model.
+    let mut n_cur = tokens.len() as i32;
+    let mut last = sampler.sample(&ctx, batch.n_tokens() - 1);
+
+    'generation: while generated < max_tokens && !brain.model.is_eog_token(last) {
+        push_piece(&brain.model, last, &mut decoder, &mut text)?;
+        generated += 1;
+
+        let mut proposal = Vec::new();
+        let mut next = last;
+        for i in 0..draft.max_tokens.min(max_tokens - generated) {
+            step.clear();
+            step.add(next, n_cur + i as i32, &[0], true).map_err(|e| e.to_string())?;
+            draft_ctx.decode(&mut step).map_err(|e| e.to_string())?;
+            next = draft_sampler.sample(&draft_ctx, 0);
+            proposal.push(next);
+            if brain.model.is_eog_token(next) {
+                break;
+            }
+        }
+
+        step.clear();
+        for (i, token) in std::iter::once(&last).chain(&proposal).enumerate() {
+            step.add(*token, n_cur + i as i32, &[0], true).map_err(|e| e.to_string())?;
+        }
+        ctx.decode(&mut step).map_err(|e| e.to_string())?;
+        drafted += proposal.len();
+
+        let mut kept = 0;
+        last = loop {
+            let token = sampler.sample(&ctx, kept as i32);
+            if kept == proposal.len() || token != proposal[kept] {
+                break token;
+            }
+            kept += 1;
+        };
+        accepted += kept;
+        for token in &proposal[..kept] {
+            if brain.model.is_eog_token(*token) {
+                break 'generation;
+            }
+            push_piece(&brain.model, *token, &mut decoder, &mut text)?;
+            generated += 1;
+        }
+
+        // Forget the rejected proposals in both caches; the draft has not yet
+        // seen its own last proposal when all of them were kept.
+        n_cur += 1 + kept as i32;
+        ctx.clear_kv_cache_seq(Some(0), Some(n_cur as u32), None).map_err(|e| e.to_string())?;
+        draft_ctx.clear_kv_cache_seq(Some(0), Some(n_cur as u32), None).map_err(|e| e.to_string())?;
+        if kept > 0 && kept == proposal.len() {
+            step.clear();
+            step.add(proposal[kept - 1], n_cur - 1, &[0], false).map_err(|e| e.to_string())?;
+            draft_ctx.decode(&mut step).map_err(|e| e.to_string())?;
+        }
     }
+
+    Ok(Generation { text, tokens: generated as i32, elapsed: start_time.elapsed(), drafted, accepted })
 }
 
+/// Asks `model` for a one-word persona label on a blocking thread, so the
+/// inference does not stall the async runtime. Returns `None` when the model
+/// cannot be loaded or the answer cannot be parsed.
+async fn classify_with_model(handle: &tauri::AppHandle, model: String, message: String, candidates: Vec<Persona>) -> Option<String> {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = handle.state::<AppState>();
+        let brain_lock = tauri::async_runtime::block_on(model_manager::lock_model(&handle, &state, &model)).ok()?;
+        let brain = brain_lock.as_ref()?;
+
+        let options = candidates.iter()
+            .map(|p| {
+                let hint = if p.web_search { " (needs current facts, news, prices or information about specific people, places or products)" } else { "" };
+                format!("{}{}", p.name.to_lowercase(), hint)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let fallback = personas::find(&candidates, personas::BASIC_ID).map_or_else(|| personas::BASIC_ID.to_string(), |p| p.name.to_lowercase());
+        let prompt = format!(
+            "<|im_start|>system\nClassify the user's message. Reply with exactly one word from: {}. \
+             Use {} when nothing else fits.<|im_end|>\n\
+             <|im_start|>user\n{}<|im_end|>\n<|im_start|>assistant\n",
+            options, fallback, message
+        );
+        let labels: Vec<String> = candidates.iter().map(|p| p.name.to_lowercase()).collect();
+        let grammar = LlamaSampler::grammar(&brain.model, &grammar::one_of(&labels), "root").ok()?;
+        let mut sampler = LlamaSampler::chain(vec![grammar, LlamaSampler::greedy()], false);
+        let generation = generate_text(brain, &prompt, &mut sampler, 16).ok()?;
+        auto_mode::parse_model_label(&generation.text, &candidates)
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Picks the concrete mode for Auto: keyword rules first, then the optional
+/// model classifier. The classifier runs on the model Auto would answer with
+/// before a persona is chosen: the chat's own, else Basic's.
+async fn resolve_auto_mode(handle: &tauri::AppHandle, state: &AppState, message: &str, lang: &str, chat_model: Option<&str>) -> MiaMode {
+    let rules = state.settings.lock().unwrap().auto_mode_rules.clone();
+    let candidates = state.personas.lock().unwrap().clone();
+    let by_rules = auto_mode::classify(message, Some(lang), &rules, &candidates);
+    let persona_id = if by_rules.confident || !rules.use_model_classifier {
+        by_rules.persona_id
+    } else {
+        let model = model_for(chat_model, &persona_for_mode(state, &MiaMode::Basic));
+        classify_with_model(handle, model, message.to_string(), candidates).await.unwrap_or(by_rules.persona_id)
+    };
+    MiaMode::from_persona_id(&persona_id)
+}
+
+pub(crate) fn resolve_chat_id(state: &AppState, chat_id: Option<String>) -> Result<String, String> {
+    let chat_id = chat_id.unwrap_or_else(|| state.active_chat_id.lock().unwrap().clone());
+    if chat_id.is_empty() { return Err(i18n::t(&i18n::ui_language(state), "no_active_chat")); }
+    Ok(chat_id)
+}
+
+/// Sets the mode of a chat (the active one when `chat_id` is omitted).
 #[tauri::command]
-pub async fn set_mia_mode(mode: MiaMode, state: State<'_, AppState>) -> Result<(), String> {
-    let mut current_mode = state.current_mode.lock().unwrap();
-    *current_mode = mode;
+pub async fn set_mia_mode(mode: MiaMode, chat_id: Option<String>, handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let chat_id = resolve_chat_id(&state, chat_id)?;
+    let mut chats = state.chats.lock().unwrap();
+    let chat = chats.get_mut(&chat_id).ok_or_else(|| i18n::t(&i18n::ui_language(&state), "chat_not_found"))?;
+    chat.mode = mode;
+    save_chats_to_disk(&handle, &chats)?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn create_new_chat(handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+pub async fn create_new_chat(mode: Option<MiaMode>, handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<String, String> {
     let new_id = Uuid::new_v4().to_string();
     let mut chats = state.chats.lock().unwrap();
     
-    chats.insert(new_id.clone(), vec![ChatMessage {
-        role: "assistant".into(),
-        content: "Hi! I'm Mia. How can i assist you today?".into(),
-        timestamp: get_now(),
-        sources: None,
-    }]);
+    chats.insert(new_id.clone(), Chat {
+        messages: vec![ChatMessage {
+            role: "assistant".into(),
+            content: i18n::t(&i18n::ui_language(&state), "greeting"),
+            timestamp: get_now(),
+            sources: None,
+            attachments: None,
+            citations: None,
+        }],
+        mode: mode.unwrap_or_default(),
+        knowledge_bases: Vec::new(),
+        model: None,
+        title: None,
+        language: None,
+    });
     
     let mut active_id = state.active_chat_id.lock().unwrap();
     *active_id = new_id.clone();
@@ -236,61 +678,110 @@ pub async fn create_new_chat(handle: tauri::AppHandle, state: State<'_, AppState
 
 #[tauri::command]
 pub async fn get_all_chats(state: State<'_, AppState>) -> Result<Vec<ChatEntry>, String> {
+    let lang = i18n::ui_language(&state);
     let chats = state.chats.lock().unwrap();
     let mut entries: Vec<ChatEntry> = Vec::new();
     
-    for (id, history) in chats.iter() {
-        let name = history.iter().find(|m| m.role == "user").or(history.first())
+    for (id, chat) in chats.iter() {
+        let history = &chat.messages;
+        let name = chat.title.clone().or_else(|| history.iter().find(|m| m.role == "user").or(history.first())
             .map(|m| {
                 let mut s = m.content.chars().take(25).collect::<String>();
                 if m.content.len() > 25 { s.push_str("..."); }
                 s
-            }).unwrap_or_else(|| "New conversation".to_string());
-
-        entries.push(ChatEntry { id: id.clone(), name, last_active: history.last().map(|m| m.timestamp).unwrap_or(0) });
+            })).unwrap_or_else(|| i18n::t(&lang, "new_conversation"));
+
+        entries.push(ChatEntry {
+            id: id.clone(),
+            name,
+            last_active: history.last().map(|m| m.timestamp).unwrap_or(0),
+            model: chat.model.clone(),
+        });
     }
-    entries.sort_by(|a, b| b.last_active.cmp(&a.last_active));
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_active));
     Ok(entries)
 }
 
+/// Makes the chat active and returns its saved mode so the UI can restore it.
 #[tauri::command]
-pub async fn switch_chat(chat_id: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn switch_chat(chat_id: String, state: State<'_, AppState>) -> Result<MiaMode, String> {
     let chats = state.chats.lock().unwrap();
-    if !chats.contains_key(&chat_id) { return Err("Chat not found".into()); }
+    let chat = chats.get(&chat_id).ok_or_else(|| i18n::t(&i18n::ui_language(&state), "chat_not_found"))?;
     let mut active_id = state.active_chat_id.lock().unwrap();
     *active_id = chat_id;
-    Ok(())
+    if let Some(lang) = &chat.language {
+        state.active_chat_language.lock().unwrap().clone_from(lang);
+    }
+    Ok(chat.mode.clone())
 }
 
 #[tauri::command]
-pub async fn ask_mia(handle: tauri::AppHandle, message: String, state: State<'_, AppState>) -> Result<MiaResponse, String> {
-    let chat_id = state.active_chat_id.lock().unwrap().clone();
-    if chat_id.is_empty() { return Err("No active chat!".into()); }
+pub async fn ask_mia(
+    handle: tauri::AppHandle,
+    message: String,
+    chat_id: Option<String>,
+    attachment_ids: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<MiaResponse, String> {
+    let chat_id = resolve_chat_id(&state, chat_id)?;
+
+    let lang = i18n::reply_language(&state, Some(&chat_id), &message);
+
+    let (user_mode, chat_model) = state.chats.lock().unwrap()
+        .get(&chat_id)
+        .map(|c| (c.mode.clone(), c.model.clone()))
+        .ok_or_else(|| i18n::t(&i18n::ui_language(&state), "chat_not_found"))?;
+    let mode = if user_mode == MiaMode::Auto {
+        let resolved = resolve_auto_mode(&handle, &state, &message, &lang, chat_model.as_deref()).await;
+        println!(">>> Auto mód döntés: {:?}", resolved);
+        resolved
+    } else {
+        user_mode
+    };
 
-    let user_mode = state.current_mode.lock().unwrap().clone();
+    let persona = persona_for_mode(&state, &mode);
+    let model = model_for(chat_model.as_deref(), &persona);
 
-    let (search_context, web_sources) = if user_mode == MiaMode::Search {
+    let (search_context, web_sources) = if persona.web_search {
         println!(">>> Mia keres a weben: {}", message);
-        fetch_web_results(&message).await
+        fetch_web_results(&state, &message).await
     } else {
         (String::new(), Vec::new())
     };
 
-    let (system_msg, temperature) = get_settings_for_mode(&message, &user_mode);
+    let has_images = {
+        let store = state.attachments.lock().unwrap();
+        attachment_ids.iter().flatten().any(|id| store.get(id).is_some_and(|a| extract::is_image(&a.mime)))
+    };
 
     {
         let mut chats = state.chats.lock().unwrap();
-        let history = chats.entry(chat_id.clone()).or_insert(Vec::new());
-        history.push(ChatMessage { role: "user".into(), content: message.clone(), timestamp: get_now(), sources: None });
+        let history = &mut chats.entry(chat_id.clone()).or_default().messages;
+        history.push(ChatMessage {
+            role: "user".into(),
+            content: message.clone(),
+            timestamp: get_now(),
+            sources: None,
+            attachments: attachment_ids.filter(|ids| !ids.is_empty()),
+            citations: None,
+        });
     }
 
+    let (document_context, citations) = {
+        let chats = state.chats.lock().unwrap();
+        match chats.get(&chat_id) {
+            Some(chat) => build_document_context(&state, chat, &message),
+            None => (String::new(), Vec::new()),
+        }
+    };
+
     // Build memory summary + lightweight RAG from older messages
     let (memory_summary, rag_context, recent_history) = {
         let chats = state.chats.lock().unwrap();
-        if let Some(history) = chats.get(&chat_id) {
+        if let Some(history) = chats.get(&chat_id).map(|c| &c.messages) {
             let max_recent = 12usize;
             let len = history.len();
-            let split_at = if len > max_recent { len - max_recent } else { 0 };
+            let split_at = len.saturating_sub(max_recent);
             let (older, recent) = history.split_at(split_at);
             let (summary, rag) = build_memory_context(older, &message);
             (summary, rag, recent.to_vec())
@@ -299,80 +790,59 @@ pub async fn ask_mia(handle: tauri::AppHandle, message: String, state: State<'_,
         }
     };
 
-    let brain_lock = state.mia_brain.lock().unwrap();
-    let brain = brain_lock.as_ref().ok_or("Mia's brain is not loaded!")?;
+    let (mut images, mut image_counts) = prompt_images(&state, &recent_history);
 
-    let ctx_params = LlamaContextParams::default().with_n_ctx(NonZeroU32::new(2048));
-    let mut ctx = brain.model.new_context(&state.backend, ctx_params).map_err(|e| e.to_string())?;
-
-    let mut system_block = system_msg;
-    if !search_context.is_empty() {
-        system_block.push_str("\n\n");
-        system_block.push_str(&search_context);
-    }
-    if !memory_summary.is_empty() {
-        system_block.push_str("\n\n");
-        system_block.push_str(&memory_summary);
-    }
-    if !rag_context.is_empty() {
-        system_block.push_str("\n\n");
-        system_block.push_str(&rag_context);
-    }
-
-    let mut prompt = format!("<|im_start|>system\n{}<|im_end|>\n", system_block);
-    for msg in recent_history.iter() {
-        prompt.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", msg.role, msg.content));
-    }
-    prompt.push_str("<|im_start|>assistant\n");
-
-    let tokens = brain.model.str_to_token(&prompt, AddBos::Never).map_err(|e| e.to_string())?;
-    let mut batch = LlamaBatch::new(2048, 1);
-    for (i, token) in tokens.iter().enumerate() {
-        let _ = batch.add(*token, i as i32, &[0], i == tokens.len() - 1);
+    let brain_lock = model_manager::lock_model(&handle, &state, &model).await?;
+    let brain = brain_lock.as_ref().ok_or_else(|| i18n::t(&lang, "brain_not_loaded"))?;
+    if brain.vision.is_none() {
+        if has_images {
+            return Err(i18n::t(&lang, "vision_not_available"));
+        }
+        // Images of earlier turns are left out for a model without vision.
+        images.clear();
+        image_counts.fill(0);
     }
-    ctx.decode(&mut batch).map_err(|e| e.to_string())?;
 
-    let start_time = Instant::now();
-    let mut generated_tokens = 0;
-    
-    let mut sampler = LlamaSampler::chain(vec![
-        LlamaSampler::temp(temperature),
-        LlamaSampler::top_k(40),
-        LlamaSampler::top_p(0.95, 1),
-        LlamaSampler::dist(rand::random()),
-    ], false);
+    let mut system = persona.system_prompt.clone();
+    system.push(' ');
+    system.push_str(&i18n::t(&lang, "answer_in_language"));
+    let contexts = [search_context, document_context, memory_summary, rag_context];
+    let budget = (context_size(brain) as usize).saturating_sub(REPLY_TOKENS);
+    let prompt = fit_prompt(&brain.model, budget, &system, contexts, recent_history, image_counts, &mut images)?;
 
-    let mut response_text = String::new();
-    let mut decoder = encoding_rs::UTF_8.new_decoder();
-    let mut n_cur = tokens.len() as i32;
-    let mut token = sampler.sample(&ctx, batch.n_tokens() - 1);
+    let mut sampler = build_sampler(&brain.model, &persona.sampling);
 
-    for _ in 0..512 {
-        if brain.model.is_eog_token(token) { break; }
-        let piece = brain.model.token_to_piece(token, &mut decoder, false, None).map_err(|e| e.to_string())?;
-        response_text.push_str(&piece);
+    let generation = if !images.is_empty() {
+        println!(">>> Képes kérdés ({} kép)", images.len());
+        generate_with_images(brain, &prompt, &images, &mut sampler, REPLY_TOKENS)?
+    } else if let Some(draft) = &brain.draft {
+        generate_speculative(brain, draft, &prompt, &mut sampler, REPLY_TOKENS)?
+    } else {
+        generate_text(brain, &prompt, &mut sampler, REPLY_TOKENS)?
+    };
+    drop(brain_lock);
 
-        batch.clear();
-        batch.add(token, n_cur, &[0], true).map_err(|e| e.to_string())?;
-        ctx.decode(&mut batch).map_err(|e| e.to_string())?;
-        token = sampler.sample(&ctx, 0);
-        n_cur += 1;
-        generated_tokens += 1;
+    let acceptance_rate = (generation.drafted > 0).then(|| generation.accepted as f32 / generation.drafted as f32);
+    if let Some(rate) = acceptance_rate {
+        println!(">>> Spekulatív dekódolás: {}/{} draft token elfogadva ({:.0}%)", generation.accepted, generation.drafted, rate * 100.0);
     }
 
-    let duration = start_time.elapsed();
+    let generated_tokens = generation.tokens;
+    let duration = generation.elapsed;
     let tps = if duration.as_secs_f32() > 0.0 { generated_tokens as f32 / duration.as_secs_f32() } else { 0.0 };
 
-    let final_resp = response_text.trim().to_string();
+    let final_resp = generation.text.trim().to_string();
 
     {
         let mut chats = state.chats.lock().unwrap();
-        if let Some(history) = chats.get_mut(&chat_id) {
-            history.push(ChatMessage { 
+        if let Some(chat) = chats.get_mut(&chat_id) {
+            chat.messages.push(ChatMessage { 
                 role: "assistant".into(), 
                 content: final_resp.clone(), 
                 timestamp: get_now(),
-                sources: if web_sources.is_empty() { None } else { Some(web_sources.clone()) } 
+                sources: if web_sources.is_empty() { None } else { Some(web_sources.clone()) },
+                attachments: None,
+                citations: if citations.is_empty() { None } else { Some(citations.clone()) },
             });
         }
         save_chats_to_disk(&handle, &chats)?;
@@ -382,33 +852,45 @@ pub async fn ask_mia(handle: tauri::AppHandle, message: String, state: State<'_,
         content: final_resp, 
         tokens: generated_tokens, 
         speed: tps, 
-        sources: web_sources 
+        sources: web_sources,
+        citations,
+        mode,
+        acceptance_rate,
     })
 }
 
+/// Loads a model (the active chat's when `model` is omitted) in the
+/// background; see `model_manager::ensure_loaded`. The emitted progress is
+/// an estimate, not llama.cpp's own load progress.
 #[tauri::command]
-pub async fn load_mia(handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    let mut brain = state.mia_brain.lock().unwrap();
-    if brain.is_some() { return Ok(()); }
-    let _ = handle.emit("mia-loading-status", true);
-    let model_path = PathBuf::from("models/mia-brain-q4.gguf");
-    let model = LlamaModel::load_from_file(&state.backend, &model_path, &LlamaModelParams::default().with_n_gpu_layers(25)).map_err(|e| e.to_string())?;
-    *brain = Some(MiaModel { model });
-    let _ = handle.emit("mia-loading-status", false);
-    Ok(())
+pub async fn load_mia(model: Option<String>, handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    validate_model(&state, model.as_deref())?;
+    let model = model.unwrap_or_else(|| active_chat_model(&state));
+    model_manager::ensure_loaded(&handle, &model).await
+}
+
+/// Picks the model of a chat (the active one when `chat_id` is omitted);
+/// `None` goes back to the persona's model.
+#[tauri::command]
+pub async fn set_chat_model(model: Option<String>, chat_id: Option<String>, handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    validate_model(&state, model.as_deref())?;
+    let chat_id = resolve_chat_id(&state, chat_id)?;
+    let mut chats = state.chats.lock().unwrap();
+    let chat = chats.get_mut(&chat_id).ok_or_else(|| i18n::t(&i18n::ui_language(&state), "chat_not_found"))?;
+    chat.model = model;
+    save_chats_to_disk(&handle, &chats)
 }
 
 #[tauri::command]
-pub async fn unload_mia(state: State<'_, AppState>) -> Result<(), String> {
-    let mut brain = state.mia_brain.lock().unwrap();
-    *brain = None;
+pub async fn unload_mia(handle: tauri::AppHandle) -> Result<(), String> {
+    model_manager::release(&handle, "manual");
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_chat_history(chat_id: String, state: State<'_, AppState>) -> Result<Vec<ChatMessage>, String> {
     let chats = state.chats.lock().unwrap();
-    Ok(chats.get(&chat_id).cloned().unwrap_or_default())
+    Ok(chats.get(&chat_id).map(|c| c.messages.clone()).unwrap_or_default())
 }
 
 #[tauri::command]
@@ -423,30 +905,62 @@ pub async fn delete_chat(chat_id: String, handle: tauri::AppHandle, state: State
     Ok(())
 }
 
+const TITLE_MAX_CHARS: u64 = 40;
+
+/// Names a chat after its first exchange and stores the title. The model
+/// answers through a JSON schema grammar, so the reply always parses; a chat
+/// that already has a title keeps it.
 #[tauri::command]
-pub async fn upload_file(path: String) -> Result<String, String> {
-    let path_obj = Path::new(&path);
-    let file_name = path_obj.file_name().unwrap_or_default().to_string_lossy();
-    let extension = path_obj.extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-
-    let content = match extension.as_str() {
-        "txt" | "md" | "rs" | "js" | "py" | "json" | "html" | "css" | "cpp" | "h" => {
-            fs::read_to_string(&path).map_err(|e| format!("Szöveges hiba: {}", e))?
-        },
-        "pdf" => {
-            extract_text(&path).map_err(|e| format!("PDF hiba: {}", e))?
-        },
-        "docx" => {
-            let mut docx = Docx::open(&path).map_err(|e| format!("Word hiba: {}", e))?;
-            let mut txt = String::new();
-            docx.read_to_string(&mut txt).map_err(|e| format!("Word olvasási hiba: {}", e))?;
-            txt
-        },
-        _ => return Err(format!("A(z) .{} formátumot még nem tanítottad meg nekem!", extension)),
+pub async fn generate_chat_title(chat_id: Option<String>, handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let chat_id = resolve_chat_id(&state, chat_id)?;
+    let (title, exchange) = {
+        let chats = state.chats.lock().unwrap();
+        let chat = chats.get(&chat_id).ok_or_else(|| i18n::t(&i18n::ui_language(&state), "chat_not_found"))?;
+        let exchange = chat.messages.iter()
+            .skip_while(|m| m.role != "user")
+            .take(2)
+            .map(|m| format!("{}: {}", m.role, m.content.chars().take(500).collect::<String>()))
+            .collect::<Vec<_>>();
+        (chat.title.clone(), exchange)
     };
+    if let Some(title) = title {
+        return Ok(title);
+    }
+    let lang = exchange.first().map_or_else(|| i18n::ui_language(&state), |m| i18n::reply_language(&state, Some(&chat_id), m));
+    if exchange.is_empty() {
+        return Ok(i18n::t(&lang, "new_conversation"));
+    }
 
-    Ok(format!("\n[DOKUMENTUM: {}]\n{}\n[DOKUMENTUM VÉGE]", file_name, content))
-}
\ No newline at end of file
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": { "title": { "type": "string", "minLength": 1, "maxLength": TITLE_MAX_CHARS } },
+        "required": ["title"]
+    });
+    let grammar = grammar::json_schema_to_gbnf(&schema)?;
+    let prompt = format!(
+        "<|im_start|>system\nGive this conversation a short title of a few words. {}<|im_end|>\n\
+         <|im_start|>user\n{}<|im_end|>\n<|im_start|>assistant\n",
+        i18n::t(&lang, "answer_in_language"),
+        exchange.join("\n")
+    );
+    let model = chat_model(&state, Some(&chat_id));
+    let text = {
+        let brain_lock = model_manager::lock_model(&handle, &state, &model).await?;
+        let brain = brain_lock.as_ref().ok_or_else(|| i18n::t(&lang, "brain_not_loaded"))?;
+        let params = SamplingParams { temperature: 0.3, ..SamplingParams::default() };
+        let mut sampler = constrained_sampler(&brain.model, &grammar, &params)?;
+        generate_text(brain, &prompt, &mut sampler, 64)?.text
+    };
+    let title = serde_json::from_str::<serde_json::Value>(&text).ok()
+        .and_then(|reply| Some(reply.get("title")?.as_str()?.trim().to_string()))
+        .filter(|title| !title.is_empty())
+        .ok_or_else(|| i18n::t(&lang, "structured_invalid_json"))?;
+    println!(">>> Beszélgetés címe: {}", title);
+
+    let mut chats = state.chats.lock().unwrap();
+    if let Some(chat) = chats.get_mut(&chat_id) {
+        chat.title = Some(title.clone());
+        save_chats_to_disk(&handle, &chats)?;
+    }
+    Ok(title)
+}
@@ -0,0 +1,87 @@
+use crate::attachments::Attachment;
+use crate::attachments::{import_image, import_text};
+use crate::commands::window::show_main_window;
+use crate::extract::MIME_PNG;
+use crate::i18n;
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+#[derive(Serialize, Clone)]
+pub struct ClipboardContent {
+    pub text: Option<String>,
+    #[serde(rename = "hasImage")]
+    pub has_image: bool,
+}
+
+fn read(app: &AppHandle) -> ClipboardContent {
+    let clipboard = app.clipboard();
+    ClipboardContent {
+        text: clipboard.read_text().ok().filter(|t| !t.trim().is_empty()),
+        has_image: clipboard.read_image().is_ok(),
+    }
+}
+
+#[tauri::command]
+pub async fn read_clipboard(app: AppHandle) -> Result<ClipboardContent, String> {
+    Ok(read(&app))
+}
+
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(rgba).map_err(|e| e.to_string())?;
+    }
+    Ok(out)
+}
+
+/// Stores the clipboard as an attachment for the next message: its text, or
+/// when there is none, its image (a screenshot) as PNG.
+#[tauri::command]
+pub async fn attach_clipboard(app: AppHandle, state: State<'_, AppState>) -> Result<Attachment, String> {
+    let lang = i18n::ui_language(&state);
+    let name = i18n::t(&lang, "clipboard_attachment_name");
+    if let Some(text) = read(&app).text {
+        let attachment = import_text(&state, name, &text)?;
+        println!(">>> Vágólap csatolva ({} karakter)", attachment.chars);
+        return Ok(attachment);
+    }
+
+    let image = app.clipboard().read_image().map_err(|_| i18n::t(&lang, "clipboard_empty"))?;
+    let png = encode_png(image.width(), image.height(), image.rgba())?;
+    let attachment = import_image(&state, format!("{}.png", name), MIME_PNG, &png)?;
+    println!(">>> Vágólap kép csatolva ({}x{})", image.width(), image.height());
+    Ok(attachment)
+}
+
+/// Shortcut handler: opens the chat and hands the clipboard to the input box
+/// through a `mia-clipboard-prefill` event.
+pub(crate) fn open_with_clipboard(app: &AppHandle) {
+    let content = read(app);
+    if let Err(e) = show_main_window(app) {
+        println!(">>> Vágólap gyorsbillentyű hiba: {}", e);
+        return;
+    }
+    let _ = app.emit("mia-clipboard-prefill", content);
+}
+
+/// Swaps the registered clipboard shortcut; an empty string only unregisters.
+/// The new shortcut is registered first, so when it is invalid or taken the
+/// old one keeps working.
+pub(crate) fn register_clipboard_shortcut(app: &AppHandle, lang: &str, old: &str, new: &str) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    if !new.is_empty() {
+        shortcuts.register(new).map_err(|e| i18n::tf(lang, "clipboard_shortcut_failed", &[new, &e.to_string()]))?;
+        println!(">>> Vágólap gyorsbillentyű: {}", new);
+    }
+    if !old.is_empty() {
+        let _ = shortcuts.unregister(old);
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use crate::commands::chat::{build_sampler, chat_model, generate_text};
+use crate::i18n;
+use crate::model_manager;
+use crate::personas::SamplingParams;
+use crate::rag::{self, Chunk, Citation};
+use crate::state::{AppState, MiaModel};
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+
+/// Characters of document text given to the model in one step.
+const STEP_BUDGET: usize = 3000;
+const SUMMARY_TOKENS: usize = 384;
+const ANSWER_TOKENS: usize = 512;
+
+#[derive(Serialize)]
+pub struct DocumentAnswer {
+    pub content: String,
+    pub citations: Vec<Citation>,
+}
+
+/// Emitted as `mia-document-progress` while a document command runs.
+#[derive(Serialize, Clone)]
+struct DocumentProgress {
+    #[serde(rename = "attachmentId")]
+    attachment_id: String,
+    /// "map", "reduce", "retrieve", "generate" or "done".
+    stage: &'static str,
+    done: usize,
+    total: usize,
+}
+
+fn emit_progress(handle: &AppHandle, attachment_id: &str, stage: &'static str, done: usize, total: usize) {
+    let _ = handle.emit("mia-document-progress", DocumentProgress {
+        attachment_id: attachment_id.to_string(),
+        stage,
+        done,
+        total,
+    });
+}
+
+fn document_sampling() -> SamplingParams {
+    SamplingParams { temperature: 0.3, ..SamplingParams::default() }
+}
+
+fn run_prompt(brain: &MiaModel, system: &str, user: &str, max_tokens: usize) -> Result<String, String> {
+    let prompt = format!(
+        "<|im_start|>system\n{}<|im_end|>\n<|im_start|>user\n{}<|im_end|>\n<|im_start|>assistant\n",
+        system, user
+    );
+    let mut sampler = build_sampler(&brain.model, &document_sampling());
+    Ok(generate_text(brain, &prompt, &mut sampler, max_tokens)?.text.trim().to_string())
+}
+
+/// Runs one step of a longer job with its own model lock, so chats can use the
+/// model between steps instead of waiting for the whole job.
+async fn run_step(handle: &AppHandle, state: &AppState, model: &str, lang: &str, system: &str, user: &str, max_tokens: usize) -> Result<String, String> {
+    let brain_lock = model_manager::lock_model(handle, state, model).await?;
+    let brain = brain_lock.as_ref().ok_or_else(|| i18n::t(lang, "brain_not_loaded"))?;
+    run_prompt(brain, system, user, max_tokens)
+}
+
+fn load_document(state: &AppState, attachment_id: &str, lang: &str) -> Result<(String, Arc<Vec<Chunk>>), String> {
+    let mut store = state.attachments.lock().unwrap();
+    let not_found = || i18n::t(lang, "attachment_not_found");
+    let name = store.get(attachment_id).ok_or_else(not_found)?.name.clone();
+    let chunks = store.read_chunks(attachment_id).ok_or_else(not_found)?;
+    Ok((name, chunks))
+}
+
+/// Packs texts into groups that each fit in one model step.
+fn group_by_budget(texts: Vec<String>) -> Vec<String> {
+    let mut groups: Vec<String> = Vec::new();
+    for text in texts {
+        match groups.last_mut() {
+            Some(group) if group.chars().count() + text.chars().count() <= STEP_BUDGET => {
+                group.push_str("\n\n");
+                group.push_str(&text);
+            }
+            _ => groups.push(text.chars().take(STEP_BUDGET).collect()),
+        }
+    }
+    groups
+}
+
+fn chunk_label(chunk: &Chunk) -> String {
+    match (&chunk.page, &chunk.section) {
+        (Some(page), _) => format!("[p. {}]\n{}", page, chunk.text.trim()),
+        (None, Some(section)) => format!("[§ {}]\n{}", section, chunk.text.trim()),
+        (None, None) => chunk.text.trim().to_string(),
+    }
+}
+
+/// Summarizes a whole attachment: every part is summarized on its own (map),
+/// then the partial summaries are merged until one remains (reduce). Runs on
+/// the model of `chat_id` (the active chat when omitted).
+#[tauri::command]
+pub async fn summarize_document(attachment_id: String, chat_id: Option<String>, handle: AppHandle, state: State<'_, AppState>) -> Result<DocumentAnswer, String> {
+    let lang = i18n::ui_language(&state);
+    let (name, chunks) = load_document(&state, &attachment_id, &lang)?;
+
+    let model = chat_model(&state, chat_id.as_deref());
+    let answer_in_language = i18n::t(&lang, "answer_in_language");
+
+    let parts = group_by_budget(chunks.iter().map(chunk_label).collect());
+    println!(">>> Dokumentum összefoglalás: {} ({} rész)", name, parts.len());
+    let map_system = format!(
+        "You summarize one part of the document \"{}\". Write a concise summary of the key points, \
+         keeping important names, numbers and page references. {}",
+        name, answer_in_language
+    );
+    let mut summaries = Vec::with_capacity(parts.len());
+    for (i, part) in parts.iter().enumerate() {
+        emit_progress(&handle, &attachment_id, "map", i, parts.len());
+        summaries.push(run_step(&handle, &state, &model, &lang, &map_system, part, SUMMARY_TOKENS).await?);
+    }
+
+    let reduce_system = format!(
+        "You combine partial summaries of the document \"{}\" into one coherent summary without repeating yourself. {}",
+        name, answer_in_language
+    );
+    while summaries.len() > 1 {
+        let count = summaries.len();
+        let mut groups = group_by_budget(summaries);
+        if groups.len() == count {
+            // Every summary fills a step on its own; shorten them so at least pairs fit together.
+            groups = group_by_budget(groups.into_iter().map(|s| s.chars().take(STEP_BUDGET / 2 - 2).collect()).collect());
+        }
+        summaries = Vec::with_capacity(groups.len());
+        for (i, group) in groups.iter().enumerate() {
+            emit_progress(&handle, &attachment_id, "reduce", i, groups.len());
+            summaries.push(run_step(&handle, &state, &model, &lang, &reduce_system, group, SUMMARY_TOKENS).await?);
+        }
+    }
+
+    emit_progress(&handle, &attachment_id, "done", 1, 1);
+    Ok(DocumentAnswer {
+        content: summaries.pop().unwrap_or_default(),
+        citations: vec![Citation { document: name, page: None, section: None }],
+    })
+}
+
+/// Answers a question strictly from one attachment, citing the pages or
+/// sections it used. Runs on the model of `chat_id` (the active chat when omitted).
+#[tauri::command]
+pub async fn ask_document(attachment_id: String, question: String, chat_id: Option<String>, handle: AppHandle, state: State<'_, AppState>) -> Result<DocumentAnswer, String> {
+    let lang = i18n::reply_language(&state, chat_id.as_deref(), &question);
+    let (name, chunks) = load_document(&state, &attachment_id, &lang)?;
+
+    emit_progress(&handle, &attachment_id, "retrieve", 0, 1);
+    let documents = [(name.as_str(), chunks.as_slice())];
+    let mut hits = rag::search(&documents, &question, 8);
+    if hits.is_empty() {
+        // A broad question ("what is this about?") matches no terms: start from the beginning.
+        hits = chunks.iter().map(|chunk| rag::Hit { document: &name, chunk, score: 0.0 }).collect();
+    }
+    let mut used = 0usize;
+    hits.retain(|hit| {
+        used += hit.chunk.text.chars().count();
+        used <= STEP_BUDGET
+    });
+    hits.sort_by_key(|hit| hit.chunk.index);
+
+    let mut excerpts = String::new();
+    let mut citations: Vec<Citation> = Vec::new();
+    for hit in &hits {
+        let citation = hit.citation();
+        excerpts.push_str(&format!("\n[{}]\n{}\n", citation.label(), hit.chunk.text.trim()));
+        if !citations.iter().any(|c| c.label() == citation.label()) {
+            citations.push(citation);
+        }
+    }
+
+    let model = chat_model(&state, chat_id.as_deref());
+    let brain_lock = model_manager::lock_model(&handle, &state, &model).await?;
+    let brain = brain_lock.as_ref().ok_or_else(|| i18n::t(&lang, "brain_not_loaded"))?;
+
+    emit_progress(&handle, &attachment_id, "generate", 0, 1);
+    let system = format!(
+        "You answer questions about the document \"{}\" using ONLY the excerpts below, never outside knowledge. \
+         If the excerpts do not contain the answer, say that the document does not cover it. \
+         Cite the excerpts you use in parentheses exactly as labelled, e.g. ({}, p. 3). {}\n\nExcerpts:{}",
+        name, name, i18n::t(&lang, "answer_in_language"), excerpts,
+    );
+    let content = run_prompt(brain, &system, &question, ANSWER_TOKENS)?;
+    drop(brain_lock);
+
+    emit_progress(&handle, &attachment_id, "done", 1, 1);
+    Ok(DocumentAnswer { content, citations })
+}
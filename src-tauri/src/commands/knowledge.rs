@@ -0,0 +1,116 @@
+use crate::commands::chat::{resolve_chat_id, save_chats_to_disk};
+use crate::i18n;
+use crate::knowledge::{self, KnowledgeBase, KnowledgeBaseInfo};
+use crate::state::AppState;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+async fn run_index(handle: &AppHandle, id: &str) -> Result<KnowledgeBaseInfo, String> {
+    let handle = handle.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn_blocking(move || knowledge::index(&handle, &id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Indexes every supported file under `path` (respecting `.gitignore`) into a
+/// new knowledge base and keeps it in sync while the directory changes.
+/// Progress is emitted as `mia-knowledge-progress`.
+#[tauri::command]
+pub async fn ingest_directory(path: String, name: Option<String>, handle: AppHandle, state: State<'_, AppState>) -> Result<KnowledgeBaseInfo, String> {
+    let lang = i18n::ui_language(&state);
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return Err(i18n::tf(&lang, "knowledge_not_directory", &[&path]));
+    }
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| root.file_name().unwrap_or_default().to_string_lossy().to_string());
+
+    let base = KnowledgeBase::new(name, path.clone());
+    let id = base.id.clone();
+    state.knowledge.lock().unwrap().insert(base)?;
+    println!(">>> Tudásbázis létrehozva: {}", path);
+
+    let result = match run_index(&handle, &id).await {
+        Ok(info) => knowledge::watch(&handle, &id).map(|_| info),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = &result {
+        // Leave no half-built base (or its files) behind.
+        println!(">>> Tudásbázis létrehozása visszavonva: {}", e);
+        knowledge::unwatch(&state, &id);
+        let removed = state.knowledge.lock().unwrap().remove(&id);
+        if let Ok(Some(base)) = removed {
+            for attachment_id in base.attachment_ids() {
+                if knowledge::is_orphaned(&state, attachment_id, &id) {
+                    let _ = state.attachments.lock().unwrap().remove(attachment_id);
+                }
+            }
+        }
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn get_knowledge_bases(state: State<'_, AppState>) -> Result<Vec<KnowledgeBaseInfo>, String> {
+    Ok(state.knowledge.lock().unwrap().list())
+}
+
+#[tauri::command]
+pub async fn reindex_knowledge_base(knowledge_base_id: String, handle: AppHandle, state: State<'_, AppState>) -> Result<KnowledgeBaseInfo, String> {
+    if state.knowledge.lock().unwrap().get(&knowledge_base_id).is_none() {
+        return Err(i18n::t(&i18n::ui_language(&state), "knowledge_not_found"));
+    }
+    run_index(&handle, &knowledge_base_id).await
+}
+
+/// Stops watching and forgets the knowledge base; its files on disk are untouched.
+#[tauri::command]
+pub async fn delete_knowledge_base(knowledge_base_id: String, handle: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let lang = i18n::ui_language(&state);
+    knowledge::unwatch(&state, &knowledge_base_id);
+    let base = state.knowledge.lock().unwrap()
+        .remove(&knowledge_base_id)?
+        .ok_or_else(|| i18n::t(&lang, "knowledge_not_found"))?;
+
+    let mut chats = state.chats.lock().unwrap();
+    for chat in chats.values_mut() {
+        chat.knowledge_bases.retain(|id| id != &knowledge_base_id);
+    }
+    save_chats_to_disk(&handle, &chats)?;
+    drop(chats);
+
+    for id in base.attachment_ids() {
+        if knowledge::is_orphaned(&state, id, &knowledge_base_id) {
+            let _ = state.attachments.lock().unwrap().remove(id);
+        }
+    }
+    println!(">>> Tudásbázis törölve: {}", base.name);
+    Ok(())
+}
+
+/// Makes a knowledge base searchable in a chat (the active one when `chat_id` is omitted).
+#[tauri::command]
+pub async fn attach_knowledge_base(knowledge_base_id: String, chat_id: Option<String>, handle: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let lang = i18n::ui_language(&state);
+    if state.knowledge.lock().unwrap().get(&knowledge_base_id).is_none() {
+        return Err(i18n::t(&lang, "knowledge_not_found"));
+    }
+    let chat_id = resolve_chat_id(&state, chat_id)?;
+    let mut chats = state.chats.lock().unwrap();
+    let chat = chats.get_mut(&chat_id).ok_or_else(|| i18n::t(&lang, "chat_not_found"))?;
+    if !chat.knowledge_bases.contains(&knowledge_base_id) {
+        chat.knowledge_bases.push(knowledge_base_id);
+    }
+    save_chats_to_disk(&handle, &chats)
+}
+
+#[tauri::command]
+pub async fn detach_knowledge_base(knowledge_base_id: String, chat_id: Option<String>, handle: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let chat_id = resolve_chat_id(&state, chat_id)?;
+    let mut chats = state.chats.lock().unwrap();
+    let chat = chats.get_mut(&chat_id).ok_or_else(|| i18n::t(&i18n::ui_language(&state), "chat_not_found"))?;
+    chat.knowledge_bases.retain(|id| id != &knowledge_base_id);
+    save_chats_to_disk(&handle, &chats)
+}
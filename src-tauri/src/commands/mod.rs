@@ -1,4 +1,12 @@
+pub mod attachments;
 pub mod chat;
+pub mod clipboard;
+pub mod documents;
+pub mod knowledge;
+pub mod models;
+pub mod personas;
+pub mod search;
 pub mod settings;
+pub mod structured;
 pub mod system;
 pub mod window;
@@ -0,0 +1,74 @@
+use crate::downloads::{self, DownloadedModel};
+use crate::i18n;
+use crate::model_manager;
+use crate::state::AppState;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+
+/// Downloads `file` of `repo` from the `modelRepositoryUrl` (Hugging Face by
+/// default) into `models/`, where it shows up in `get_available_models`.
+/// Progress is emitted as `mia-download-progress`; an interrupted download
+/// continues where it stopped. The checksum is `sha256` when given, otherwise
+/// the one the server publishes.
+#[tauri::command]
+pub async fn download_model(
+    repo: String,
+    file: String,
+    revision: Option<String>,
+    sha256: Option<String>,
+    handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DownloadedModel, String> {
+    let lang = i18n::ui_language(&state);
+    let name = file.rsplit('/').next().unwrap_or_default().to_string();
+    let revision = revision.unwrap_or_else(|| "main".to_string());
+    for path in [&repo, &revision, &file] {
+        if !downloads::is_safe_remote_path(path) {
+            return Err(i18n::tf(&lang, "download_invalid_path", &[path]));
+        }
+    }
+    if !downloads::is_safe_file_name(&name) || !name.ends_with(".gguf") {
+        return Err(i18n::tf(&lang, "download_not_gguf", &[&file]));
+    }
+    let (base, offline) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.model_repository_url.clone(), settings.offline_mode)
+    };
+    if offline {
+        return Err(i18n::t(&lang, "download_offline"));
+    }
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut running = state.downloads.lock().unwrap();
+        if running.contains_key(&name) {
+            return Err(i18n::tf(&lang, "download_in_progress", &[&name]));
+        }
+        running.insert(name.clone(), Arc::clone(&cancel));
+    }
+
+    let url = downloads::file_url(&base, &repo, &revision, &file);
+    println!(">>> Modell letöltése: {}", url);
+    let progress = |progress| {
+        let _ = handle.emit("mia-download-progress", progress);
+    };
+    let dir = Path::new(model_manager::MODELS_DIR);
+    let result = downloads::download(dir, &lang, &url, &name, sha256, &cancel, progress).await;
+    state.downloads.lock().unwrap().remove(&name);
+    if result.is_ok() {
+        model_manager::forget_layout(&state, &name);
+    }
+    result
+}
+
+/// Stops a running download; its part file is kept for resuming.
+#[tauri::command]
+pub async fn cancel_download(file: String, state: State<'_, AppState>) -> Result<(), String> {
+    let name = file.rsplit('/').next().unwrap_or_default();
+    if let Some(cancel) = state.downloads.lock().unwrap().get(name) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
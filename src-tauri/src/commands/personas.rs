@@ -0,0 +1,69 @@
+use crate::commands::chat::validate_model;
+use crate::i18n;
+use crate::personas::{self, Persona};
+use crate::state::AppState;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+fn persist(app: &AppHandle, list: &[Persona]) -> Result<(), String> {
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    personas::save_personas(&app_dir.join("personas.json"), list)
+}
+
+#[tauri::command]
+pub async fn get_personas(state: State<'_, AppState>) -> Result<Vec<Persona>, String> {
+    Ok(state.personas.lock().unwrap().clone())
+}
+
+/// Creates a persona when `id` is empty, otherwise updates the existing one.
+#[tauri::command]
+pub async fn save_persona(mut persona: Persona, app: AppHandle, state: State<'_, AppState>) -> Result<Persona, String> {
+    let lang = i18n::ui_language(&state);
+    if persona.name.trim().is_empty() {
+        return Err(i18n::t(&lang, "persona_name_empty"));
+    }
+    validate_model(&state, persona.model.as_deref())?;
+
+    let mut list = state.personas.lock().unwrap();
+    if persona.id.is_empty() {
+        persona.id = Uuid::new_v4().to_string();
+        persona.built_in = false;
+        list.push(persona.clone());
+    } else {
+        let existing = list.iter_mut().find(|p| p.id == persona.id).ok_or_else(|| i18n::t(&lang, "persona_not_found"))?;
+        persona.built_in = existing.built_in;
+        *existing = persona.clone();
+    }
+
+    persist(&app, &list)?;
+    Ok(persona)
+}
+
+#[tauri::command]
+pub async fn delete_persona(persona_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let lang = i18n::ui_language(&state);
+    let mut list = state.personas.lock().unwrap();
+    let persona = personas::find(&list, &persona_id).ok_or_else(|| i18n::t(&lang, "persona_not_found"))?;
+    if persona.built_in {
+        return Err(i18n::t(&lang, "persona_builtin_delete"));
+    }
+    list.retain(|p| p.id != persona_id);
+    persist(&app, &list)
+}
+
+/// Restores a built-in persona to its shipped defaults.
+#[tauri::command]
+pub async fn reset_persona(persona_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<Persona, String> {
+    let default = personas::default_personas()
+        .into_iter()
+        .find(|p| p.id == persona_id)
+        .ok_or_else(|| i18n::t(&i18n::ui_language(&state), "persona_reset_only_builtin"))?;
+
+    let mut list = state.personas.lock().unwrap();
+    match list.iter_mut().find(|p| p.id == persona_id) {
+        Some(existing) => *existing = default.clone(),
+        None => list.push(default.clone()),
+    }
+    persist(&app, &list)?;
+    Ok(default)
+}
@@ -0,0 +1,162 @@
+use crate::extract;
+use crate::state::{AppState, WebSource};
+use crate::web_cache::{self, CachedSearch, CachedValue};
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use tauri::State;
+
+const SEARCH_PROVIDER: &str = "duckduckgo";
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+const PAGE_TEXT_LIMIT: usize = 1500;
+
+struct CachePolicy {
+    ttl_secs: u64,
+    max_bytes: u64,
+    offline: bool,
+    fetch_top_result: bool,
+}
+
+fn cache_policy(state: &AppState) -> CachePolicy {
+    let settings = state.settings.lock().unwrap();
+    CachePolicy {
+        ttl_secs: settings.web_cache_ttl_minutes * 60,
+        max_bytes: settings.web_cache_max_mb * 1024 * 1024,
+        offline: settings.offline_mode,
+        fetch_top_result: settings.fetch_top_result,
+    }
+}
+
+fn build_client() -> Option<reqwest::Client> {
+    reqwest::Client::builder().user_agent(USER_AGENT).build().ok()
+}
+
+pub async fn fetch_web_results(state: &AppState, query: &str) -> (String, Vec<WebSource>) {
+    let policy = cache_policy(state);
+    let key = web_cache::search_key(SEARCH_PROVIDER, query);
+
+    let cached = state.web_cache.lock().unwrap().get(&key, policy.ttl_secs, policy.offline);
+    if let Some(CachedValue::Search(hit)) = cached {
+        println!(">>> Keresés a gyorsítótárból: {}", query);
+        return (hit.context, hit.sources);
+    }
+    if policy.offline {
+        return ("Offline mode: no cached search results for this question.".into(), Vec::new());
+    }
+
+    let (mut context, sources) = search_duckduckgo(query).await;
+    if sources.is_empty() {
+        return (context, sources);
+    }
+
+    if let Some(top) = sources.first().filter(|_| policy.fetch_top_result) {
+        if let Some(page_text) = fetch_page_text(state, &top.url).await {
+            context.push_str(&format!("Top result excerpt ({}):\n{}\n", top.title, page_text));
+        }
+    }
+
+    state.web_cache.lock().unwrap().put(
+        key,
+        CachedValue::Search(CachedSearch { context: context.clone(), sources: sources.clone() }),
+        policy.max_bytes,
+    );
+    (context, sources)
+}
+
+async fn search_duckduckgo(query: &str) -> (String, Vec<WebSource>) {
+    let mut sources = Vec::new();
+    let client = match build_client() {
+        Some(c) => c,
+        None => return ("Search failed to initialize.".into(), Vec::new()),
+    };
+
+    let url = match reqwest::Url::parse_with_params("https://html.duckduckgo.com/html/", &[("q", query)]) {
+        Ok(url) => url,
+        Err(_) => return ("Search failed to initialize.".into(), Vec::new()),
+    };
+    let res = match client.get(url).send().await {
+        Ok(r) => r,
+        Err(_) => return ("Failed to connect to search engine.".into(), Vec::new()),
+    };
+
+    let html_content = res.text().await.unwrap_or_default();
+    let document = Html::parse_document(&html_content);
+    let result_selector = Selector::parse(".result__body").unwrap();
+    let title_selector = Selector::parse(".result__a").unwrap();
+    let snippet_selector = Selector::parse(".result__snippet").unwrap();
+
+    let mut search_context = String::from("\nWeb Search Data (Current Date: 2026):\n");
+
+    for result in document.select(&result_selector).take(5) {
+        let title_el = result.select(&title_selector).next();
+        let snippet_el = result.select(&snippet_selector).next();
+
+        if let (Some(t_el), Some(s_el)) = (title_el, snippet_el) {
+            let title = t_el.text().collect::<String>();
+            let snippet = s_el.text().collect::<String>();
+            let raw_url = t_el.value().attr("href").unwrap_or_default();
+
+            if !title.is_empty() && !raw_url.is_empty() {
+                let clean_url = if raw_url.starts_with("//") {
+                    format!("https:{}", raw_url)
+                } else if raw_url.contains("http") {
+                    raw_url.to_string()
+                } else {
+                    format!("https://duckduckgo.com{}", raw_url)
+                };
+
+                sources.push(WebSource {
+                    title: title.clone(),
+                    url: clean_url.clone()
+                });
+
+                search_context.push_str(&format!("- Source: {}\n  Content: {}\n\n", title, snippet));
+            }
+        }
+    }
+
+    if sources.is_empty() {
+        (String::from("No search results found on the web."), Vec::new())
+    } else {
+        (search_context, sources)
+    }
+}
+
+/// Downloads a page and returns its readable text, going through the web cache.
+pub async fn fetch_page_text(state: &AppState, url: &str) -> Option<String> {
+    let policy = cache_policy(state);
+    let key = web_cache::page_key(url);
+
+    let cached = state.web_cache.lock().unwrap().get(&key, policy.ttl_secs, policy.offline);
+    if let Some(CachedValue::Page { text }) = cached {
+        return Some(text);
+    }
+    if policy.offline {
+        return None;
+    }
+
+    let client = build_client()?;
+    let html_content = client.get(url).send().await.ok()?.text().await.ok()?;
+    let text: String = extract::html_to_text(&html_content).chars().take(PAGE_TEXT_LIMIT).collect();
+    if text.is_empty() {
+        return None;
+    }
+
+    state.web_cache.lock().unwrap().put(key, CachedValue::Page { text: text.clone() }, policy.max_bytes);
+    Some(text)
+}
+
+#[tauri::command]
+pub async fn get_web_cache_info(state: State<'_, AppState>) -> Result<Value, String> {
+    let cache = state.web_cache.lock().unwrap();
+    Ok(json!({
+        "entries": cache.len(),
+        "bytes": cache.total_size(),
+    }))
+}
+
+#[tauri::command]
+pub async fn clear_web_cache(state: State<'_, AppState>) -> Result<(), String> {
+    state.web_cache.lock().unwrap().clear()?;
+    println!(">>> Webes gyorsítótár törölve.");
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::commands::clipboard::register_clipboard_shortcut;
+use crate::i18n;
 use crate::state::{AppSettings, AppState};
 use std::fs;
 use tauri::{AppHandle, Manager, State};
@@ -22,8 +24,18 @@ pub async fn save_settings(
     state: State<'_, AppState>,
     app: AppHandle,
 ) -> Result<(), String> {
+    if settings.max_upload_mb == 0 {
+        return Err(i18n::t(&i18n::ui_language(&state), "settings_invalid_upload_limit"));
+    }
+
+    let old_shortcut = state.settings.lock().unwrap().clipboard_shortcut.clone();
+    if old_shortcut != settings.clipboard_shortcut {
+        register_clipboard_shortcut(&app, &i18n::ui_language(&state), &old_shortcut, &settings.clipboard_shortcut)?;
+    }
+
     let mut list = state.games_list.lock().unwrap();
     *list = settings.games.clone();
+    *state.settings.lock().unwrap() = settings.clone();
 
     let config_dir = app.path().app_config_dir().unwrap();
     fs::create_dir_all(&config_dir).ok();
@@ -34,3 +46,9 @@ pub async fn save_settings(
     fs::write(&file_path, json_data).map_err(|e| e.to_string())?;
     Ok(())
 }
+
+/// A UI text from the backend's message catalogs, in the current UI language.
+#[tauri::command]
+pub async fn translate(key: String, state: State<'_, AppState>) -> Result<String, String> {
+    Ok(i18n::t(&i18n::ui_language(&state), &key))
+}
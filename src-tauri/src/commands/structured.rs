@@ -0,0 +1,64 @@
+use crate::commands::chat::{chat_model, constrained_sampler, generate_text};
+use crate::grammar;
+use crate::i18n;
+use crate::model_manager;
+use crate::personas::SamplingParams;
+use crate::state::AppState;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+const DEFAULT_MAX_TOKENS: usize = 512;
+
+#[derive(Serialize)]
+pub struct StructuredOutput {
+    pub text: String,
+    /// The parsed reply when a JSON schema was given.
+    pub json: Option<Value>,
+}
+
+/// Generates with the model of `chat_id` (the active chat when omitted),
+/// constrained by a GBNF `grammar` (with a `root` rule) or by a JSON schema
+/// converted to one. Exactly one of the two must be given.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_structured(
+    prompt: String,
+    grammar: Option<String>,
+    json_schema: Option<Value>,
+    system: Option<String>,
+    max_tokens: Option<usize>,
+    chat_id: Option<String>,
+    handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<StructuredOutput, String> {
+    let lang = i18n::reply_language(&state, chat_id.as_deref(), &prompt);
+    let gbnf = match (grammar, &json_schema) {
+        (Some(grammar), None) => grammar,
+        (None, Some(schema)) => grammar::json_schema_to_gbnf(schema)
+            .map_err(|e| i18n::tf(&lang, "structured_invalid_schema", &[&e]))?,
+        _ => return Err(i18n::t(&lang, "structured_needs_grammar")),
+    };
+    let prompt = format!(
+        "<|im_start|>system\n{}<|im_end|>\n<|im_start|>user\n{}<|im_end|>\n<|im_start|>assistant\n",
+        system.unwrap_or_else(|| i18n::t(&lang, "answer_in_language")),
+        prompt
+    );
+
+    let model = chat_model(&state, chat_id.as_deref());
+    let text = {
+        let brain_lock = model_manager::lock_model(&handle, &state, &model).await?;
+        let brain = brain_lock.as_ref().ok_or_else(|| i18n::t(&lang, "brain_not_loaded"))?;
+        let params = SamplingParams { temperature: 0.3, ..SamplingParams::default() };
+        let mut sampler = constrained_sampler(&brain.model, &gbnf, &params)
+            .map_err(|e| i18n::tf(&lang, "structured_invalid_grammar", &[&e]))?;
+        generate_text(brain, &prompt, &mut sampler, max_tokens.unwrap_or(DEFAULT_MAX_TOKENS))?.text
+    };
+
+    let json = match json_schema {
+        // A reply cut off by `max_tokens` is not complete JSON.
+        Some(_) => Some(serde_json::from_str(text.trim()).map_err(|_| i18n::t(&lang, "structured_invalid_json"))?),
+        None => None,
+    };
+    Ok(StructuredOutput { text, json })
+}
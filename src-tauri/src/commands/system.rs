@@ -1,9 +1,11 @@
+use crate::model_manager::{self, ModelInfo};
 use crate::state::AppState;
 use serde_json::{json, Value};
 use tauri::State;
 
 #[tauri::command]
 pub async fn get_system_stats(state: State<'_, AppState>) -> Result<Value, String> {
+    let backend = model_manager::active_backend(&state);
     let mut sys = state.sys.lock().unwrap();
 
     sys.refresh_cpu_usage();
@@ -25,6 +27,20 @@ pub async fn get_system_stats(state: State<'_, AppState>) -> Result<Value, Strin
             "display": format!("{:.1} / {:.1} GB",
                 used_mem as f64 / 1024.0 / 1024.0 / 1024.0,
                 total_mem as f64 / 1024.0 / 1024.0 / 1024.0)
-        }
+        },
+        "backend": backend
     }))
 }
+
+/// Model files in `models/` that chats and personas can pick.
+#[tauri::command]
+pub async fn get_available_models() -> Result<Vec<String>, String> {
+    Ok(model_manager::available_models())
+}
+
+/// File, architecture, size, quantization and load details of the loaded
+/// model; `None` when no model is loaded.
+#[tauri::command]
+pub async fn get_model_info(state: State<'_, AppState>) -> Result<Option<ModelInfo>, String> {
+    Ok(model_manager::loaded_model_info(&state))
+}
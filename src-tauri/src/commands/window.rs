@@ -1,9 +1,10 @@
-use crate::commands::chat;
+use crate::commands::chat::active_chat_model;
+use crate::model_manager;
 use crate::state::AppState;
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Manager};
 
 #[tauri::command]
-pub async fn toggle_main_window(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn toggle_main_window(app: AppHandle) -> Result<(), String> {
     let main = app
         .get_webview_window("main")
         .ok_or("Main window not found")?;
@@ -15,41 +16,38 @@ pub async fn toggle_main_window(app: AppHandle, state: State<'_, AppState>) -> R
 
         main.hide().map_err(|e| e.to_string())?;
         floater.show().map_err(|e| e.to_string())?;
-       
-        let mut brain = state.mia_brain.lock().unwrap();
-        if brain.is_some() {
-            let old_brain = brain.take(); 
-            
-            std::mem::drop(old_brain); 
-            let _ = app.emit("mia-loading-status", false);
-            println!(">>> Mia elrejtve: VRAM kényszerítve felszabadítva.");
-        }
+        model_manager::on_window_hidden(&app);
     } else {
-        let _ = chat::load_mia(app.clone(), state).await;
-
-        main.show().map_err(|e| e.to_string())?;
-        main.set_focus().map_err(|e| e.to_string())?;
-        floater.hide().map_err(|e| e.to_string())?;
+        show_main_window(&app)?;
     }
     Ok(())
 }
 
+/// Brings the chat window to the front and starts loading the model if needed;
+/// progress is reported through events while the window is already usable.
+pub(crate) fn show_main_window(app: &AppHandle) -> Result<(), String> {
+    let main = app
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    let floater = app
+        .get_webview_window("floater")
+        .ok_or("Floater window not found")?;
+
+    main.show().map_err(|e| e.to_string())?;
+    main.set_focus().map_err(|e| e.to_string())?;
+    floater.hide().map_err(|e| e.to_string())?;
+    model_manager::preload(app, active_chat_model(&app.state::<AppState>()));
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn hide_main_window(app: tauri::AppHandle, state: tauri::State<'_, AppState>) -> Result<(), String> {
+pub async fn hide_main_window(app: tauri::AppHandle) -> Result<(), String> {
     let main = app.get_webview_window("main").ok_or("Main window not found")?;
     let floater = app.get_webview_window("floater").ok_or("Floater window not found")?;
 
     main.hide().map_err(|e| e.to_string())?;
     floater.show().map_err(|e| e.to_string())?;
-
-    let mut brain = state.mia_brain.lock().unwrap();
-    if brain.is_some() {
-        let old_brain = brain.take();
-        std::mem::drop(old_brain);
-        
-        let _ = app.emit("mia-loading-status", false);
-        println!(">>> Egyedi X gomb: Mia elrejtve, VRAM kényszerítve felszabadítva.");
-    }
+    model_manager::on_window_hidden(&app);
 
     Ok(())
 }
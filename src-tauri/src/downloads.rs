@@ -0,0 +1,294 @@
+use reqwest::header::{HeaderMap, ETAG, RANGE};
+use reqwest::{redirect, StatusCode};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::i18n;
+
+const USER_AGENT: &str = concat!("mia/", env!("CARGO_PKG_VERSION"));
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reported through the `progress` callback of [`download`]; the command
+/// emits it as `mia-download-progress`.
+#[derive(Serialize, Clone)]
+pub struct DownloadProgress {
+    pub file: String,
+    /// "download", "verify" or "done".
+    pub stage: &'static str,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DownloadedModel {
+    pub name: String,
+    pub sha256: String,
+    pub size: u64,
+    /// False when neither the caller nor the server gave a checksum to compare with.
+    pub verified: bool,
+}
+
+/// Hugging Face-style file URL: `<base>/<repo>/resolve/<revision>/<file>`.
+pub fn file_url(base: &str, repo: &str, revision: &str, file: &str) -> String {
+    format!("{}/{}/resolve/{}/{}", base.trim_end_matches('/'), repo.trim_matches('/'), revision, file)
+}
+
+/// A repository, revision or file path made of plain `/`-separated names:
+/// no `.` / `..` segments, backslashes, drive letters or empty segments.
+pub fn is_safe_remote_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains(['\\', ':'])
+        && path.split('/').all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+/// A file name that stays inside the directory it is joined to.
+pub fn is_safe_file_name(name: &str) -> bool {
+    is_safe_remote_path(name) && Path::new(name).file_name().and_then(|n| n.to_str()) == Some(name)
+}
+
+fn part_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.part", name))
+}
+
+fn as_sha256(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches("W/").trim_matches('"').to_ascii_lowercase();
+    (value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())).then_some(value)
+}
+
+fn header_sha256(headers: &HeaderMap) -> Option<String> {
+    ["x-linked-etag", ETAG.as_str()]
+        .iter()
+        .filter_map(|name| headers.get(*name)?.to_str().ok())
+        .find_map(as_sha256)
+}
+
+/// The checksum the server publishes for a file. Hugging Face answers with a
+/// redirect to its CDN and puts the SHA256 of LFS files in `X-Linked-Etag`,
+/// so the redirect is not followed.
+async fn remote_sha256(url: &str) -> Option<String> {
+    let client = reqwest::Client::builder().user_agent(USER_AGENT).redirect(redirect::Policy::none()).build().ok()?;
+    let response = client.head(url).send().await.ok()?;
+    header_sha256(response.headers())
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Downloads `url` into `<dir>/<name>`. Data goes to `<name>.part` first, so
+/// a cancelled or failed download resumes with a range request next time;
+/// the file is renamed into place only after its SHA256 matched `expected`
+/// (or the server's checksum when `expected` is `None`).
+#[allow(clippy::too_many_arguments)]
+pub async fn download(
+    dir: &Path,
+    lang: &str,
+    url: &str,
+    name: &str,
+    expected: Option<String>,
+    cancel: &AtomicBool,
+    progress: impl Fn(DownloadProgress) + Sync,
+) -> Result<DownloadedModel, String> {
+    let emit = |stage, downloaded, total| progress(DownloadProgress { file: name.to_string(), stage, downloaded, total });
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let part = part_path(dir, name);
+    let resume_from = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+    let expected = match expected {
+        Some(hash) => Some(as_sha256(&hash).ok_or_else(|| i18n::tf(lang, "download_bad_checksum", &[&hash]))?),
+        None => remote_sha256(url).await,
+    };
+
+    let client = reqwest::Client::builder().user_agent(USER_AGENT).build().map_err(|e| e.to_string())?;
+    let mut request = client.get(url);
+    if resume_from > 0 {
+        request = request.header(RANGE, format!("bytes={}-", resume_from));
+    }
+    let mut response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+
+    let mut downloaded = resume_from;
+    if status == StatusCode::RANGE_NOT_SATISFIABLE && resume_from > 0 {
+        // Nothing left past the end of the part file: it is complete.
+        println!(">>> Letöltés már teljes: {}", name);
+    } else if !status.is_success() {
+        return Err(format!("HTTP {}: {}", status, url));
+    } else {
+        let mut file = if status == StatusCode::PARTIAL_CONTENT {
+            println!(">>> Letöltés folytatása: {} ({} bájttól)", name, resume_from);
+            OpenOptions::new().append(true).open(&part)
+        } else {
+            // The server ignored the range; start over.
+            downloaded = 0;
+            File::create(&part)
+        }
+        .map_err(|e| e.to_string())?;
+        let total = response.content_length().map(|len| downloaded + len);
+
+        let mut last_emit = Instant::now();
+        emit("download", downloaded, total);
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            if cancel.load(Ordering::Relaxed) {
+                println!(">>> Letöltés megszakítva: {} ({} bájt megmarad)", name, downloaded);
+                return Err(i18n::t(lang, "download_cancelled"));
+            }
+            file.write_all(&chunk).map_err(|e| e.to_string())?;
+            downloaded += chunk.len() as u64;
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                emit("download", downloaded, total);
+                last_emit = Instant::now();
+            }
+        }
+        file.flush().map_err(|e| e.to_string())?;
+    }
+
+    emit("verify", downloaded, Some(downloaded));
+    let sha256 = {
+        let part = part.clone();
+        tauri::async_runtime::spawn_blocking(move || hash_file(&part))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?
+    };
+    if let Some(expected) = &expected {
+        if sha256 != *expected {
+            // A corrupt part file would only be resumed into another mismatch.
+            let _ = fs::remove_file(&part);
+            return Err(i18n::tf(lang, "download_checksum_mismatch", &[name]));
+        }
+    }
+    fs::rename(&part, dir.join(name)).map_err(|e| e.to_string())?;
+
+    emit("done", downloaded, Some(downloaded));
+    println!(">>> Modell letöltve: {} ({} bájt, ellenőrizve: {})", name, downloaded, expected.is_some());
+    Ok(DownloadedModel { name: name.to_string(), sha256, size: downloaded, verified: expected.is_some() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    /// Serves `BODY` over plain HTTP/1.1 and records the `Range` header of
+    /// every request. With `ranges` false the header is ignored.
+    fn serve(ranges: bool) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/model.gguf", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut range = None;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        range = Some(value.trim().trim_end_matches('-').to_string());
+                    }
+                    line.clear();
+                }
+                log.lock().unwrap().push(range.clone());
+                let start = range.filter(|_| ranges).map(|r| r.parse::<usize>().unwrap());
+                let (status, body) = match start {
+                    Some(start) if start >= BODY.len() => ("416 Range Not Satisfiable", &[][..]),
+                    Some(start) => ("206 Partial Content", &BODY[start..]),
+                    None => ("200 OK", BODY),
+                };
+                let head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len());
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(body);
+            }
+        });
+        (url, seen)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mia-download-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn run(dir: &Path, url: &str, expected: &str) -> Result<DownloadedModel, String> {
+        let cancel = AtomicBool::new(false);
+        tauri::async_runtime::block_on(download(dir, "en", url, "model.gguf", Some(expected.to_string()), &cancel, |_| {}))
+    }
+
+    fn body_sha256() -> String {
+        crate::attachments::hash_bytes(BODY)
+    }
+
+    #[test]
+    fn downloads_whole_file() {
+        let dir = temp_dir("whole");
+        let (url, seen) = serve(true);
+        let model = run(&dir, &url, &body_sha256()).unwrap();
+        assert!(model.verified);
+        assert_eq!(model.size, BODY.len() as u64);
+        assert_eq!(fs::read(dir.join("model.gguf")).unwrap(), BODY);
+        assert!(!part_path(&dir, "model.gguf").exists());
+        assert_eq!(*seen.lock().unwrap(), vec![None]);
+    }
+
+    #[test]
+    fn resumes_part_file_with_range() {
+        let dir = temp_dir("resume");
+        fs::write(part_path(&dir, "model.gguf"), &BODY[..10]).unwrap();
+        let (url, seen) = serve(true);
+        run(&dir, &url, &body_sha256()).unwrap();
+        assert_eq!(fs::read(dir.join("model.gguf")).unwrap(), BODY);
+        assert_eq!(*seen.lock().unwrap(), vec![Some("10".to_string())]);
+    }
+
+    #[test]
+    fn restarts_when_server_ignores_range() {
+        let dir = temp_dir("restart");
+        fs::write(part_path(&dir, "model.gguf"), b"stale bytes").unwrap();
+        let (url, _) = serve(false);
+        run(&dir, &url, &body_sha256()).unwrap();
+        assert_eq!(fs::read(dir.join("model.gguf")).unwrap(), BODY);
+    }
+
+    #[test]
+    fn complete_part_file_is_verified_on_416() {
+        let dir = temp_dir("complete");
+        fs::write(part_path(&dir, "model.gguf"), BODY).unwrap();
+        let (url, seen) = serve(true);
+        let model = run(&dir, &url, &body_sha256()).unwrap();
+        assert_eq!(model.size, BODY.len() as u64);
+        assert_eq!(fs::read(dir.join("model.gguf")).unwrap(), BODY);
+        assert_eq!(*seen.lock().unwrap(), vec![Some(BODY.len().to_string())]);
+    }
+
+    #[test]
+    fn checksum_mismatch_removes_part_file() {
+        let dir = temp_dir("mismatch");
+        let (url, _) = serve(true);
+        let err = run(&dir, &url, &"0".repeat(64)).err().unwrap();
+        assert_eq!(err, i18n::tf("en", "download_checksum_mismatch", &["model.gguf"]));
+        assert!(!part_path(&dir, "model.gguf").exists());
+        assert!(!dir.join("model.gguf").exists());
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        for path in ["", "..", "a/../b", "a//b", "/abs", "a\\b", "C:model.gguf", "./a"] {
+            assert!(!is_safe_remote_path(path), "{}", path);
+        }
+        assert!(is_safe_remote_path("TheBloke/Model-GGUF"));
+        assert!(is_safe_file_name("model.Q4_K_M.gguf"));
+        assert!(!is_safe_file_name("dir/model.gguf"));
+    }
+}
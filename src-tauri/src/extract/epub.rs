@@ -0,0 +1,141 @@
+use super::{html_to_text, read_zip_entry, Extractor, Failure, MIME_EPUB};
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+
+/// EPUB books: the chapters listed in the package spine, in reading order.
+pub struct Epub;
+
+fn selector(name: &str) -> Selector {
+    Selector::parse(name).unwrap()
+}
+
+/// Decodes the `%XX` escapes of an href ("Chapter%201.xhtml"); malformed
+/// escapes are kept as written.
+fn percent_decode(href: &str) -> String {
+    let bytes = href.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The archive path of a manifest href, which is relative to the directory
+/// of the package file (`base`) and may step out of it with `..`.
+fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+    let mut parts: Vec<String> = base.split('/').filter(|p| !p.is_empty()).map(str::to_string).collect();
+    if href.starts_with('/') {
+        parts.clear();
+    }
+    for segment in percent_decode(href).split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(segment.to_string()),
+        }
+    }
+    parts.join("/")
+}
+
+impl Extractor for Epub {
+    fn name(&self) -> &'static str {
+        "EPUB"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_EPUB
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        let container = Html::parse_document(&read_zip_entry(bytes, "META-INF/container.xml")?);
+        let package_path = container.select(&selector("rootfile"))
+            .find_map(|e| e.value().attr("full-path"))
+            .ok_or("EPUB package file not found")?
+            .to_string();
+        let base = package_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+
+        let package = Html::parse_document(&read_zip_entry(bytes, &package_path)?);
+        let manifest: HashMap<&str, &str> = package.select(&selector("item"))
+            .filter_map(|e| Some((e.value().attr("id")?, e.value().attr("href")?)))
+            .collect();
+
+        let mut out = String::new();
+        for itemref in package.select(&selector("itemref")) {
+            let Some(href) = itemref.value().attr("idref").and_then(|id| manifest.get(id)) else { continue };
+            let Ok(chapter) = read_zip_entry(bytes, &resolve_href(base, href)) else { continue };
+            let text = html_to_text(&chapter);
+            if !text.is_empty() {
+                out.push_str(&text);
+                out.push_str("\n\n");
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    /// A book whose package sits in `OEBPS/`, with one chapter under it
+    /// (escaped space, fragment) and one next to it (`../`).
+    fn book() -> Vec<u8> {
+        let entries = [
+            ("mimetype", MIME_EPUB),
+            (
+                "META-INF/container.xml",
+                r#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#,
+            ),
+            (
+                "OEBPS/content.opf",
+                r#"<package><manifest>
+                    <item id="one" href="Text/Chapter%201.xhtml#start"/>
+                    <item id="two" href="../shared/./two.xhtml"/>
+                </manifest><spine><itemref idref="two"/><itemref idref="one"/></spine></package>"#,
+            ),
+            ("OEBPS/Text/Chapter 1.xhtml", "<html><body><p>First chapter</p></body></html>"),
+            ("shared/two.xhtml", "<html><body><p>Second chapter</p></body></html>"),
+        ];
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn reads_escaped_and_parent_relative_chapters_in_spine_order() {
+        let text = Epub.extract(&book()).unwrap_or_else(|_| panic!("extraction failed"));
+        let second = text.find("Second chapter").expect("chapter outside the package directory");
+        let first = text.find("First chapter").expect("chapter with an escaped name");
+        assert!(second < first);
+    }
+
+    #[test]
+    fn resolves_hrefs_against_the_package_directory() {
+        assert_eq!(resolve_href("OEBPS", "Text/a%20b.xhtml#top"), "OEBPS/Text/a b.xhtml");
+        assert_eq!(resolve_href("OEBPS/content", "../../x.xhtml"), "x.xhtml");
+        assert_eq!(resolve_href("", "x%2"), "x%2");
+        assert_eq!(resolve_href("OEBPS", "%C3%A9.xhtml"), "OEBPS/é.xhtml");
+    }
+}
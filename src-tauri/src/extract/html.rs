@@ -0,0 +1,244 @@
+use super::{text, Extractor, Failure, MIME_HTML};
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// HTML files and fetched web pages as readable Markdown-like text: headings,
+/// lists, tables and links are kept, scripts, styles and page chrome dropped.
+pub struct HtmlPage;
+
+impl Extractor for HtmlPage {
+    fn name(&self) -> &'static str {
+        "HTML"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_HTML
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        Ok(html_to_text(&text::decode(bytes)))
+    }
+}
+
+const SKIPPED: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "canvas", "iframe",
+    "nav", "aside", "button", "select",
+];
+/// Skipped only as direct children of `<body>`, where they are the page's
+/// banner and footer; inside an article they are part of the content.
+const PAGE_CHROME: &[&str] = &["header", "footer"];
+const BLOCKS: &[&str] = &[
+    "p", "div", "section", "article", "main", "blockquote", "figure", "figcaption",
+    "dl", "dt", "dd", "address", "details", "summary", "hr",
+];
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+    /// One entry per open list: `None` for `<ul>`, the next number for `<ol>`.
+    lists: Vec<Option<usize>>,
+    /// Right after a list marker, where block breaks would split the item.
+    item_start: bool,
+    /// Where the outermost open list begins in `out`.
+    list_start: usize,
+}
+
+impl Writer {
+    fn push(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.item_start = false;
+    }
+
+    fn newline(&mut self) {
+        if !self.item_start && !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.item_start && !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Drops blank lines inside lists, left there by paragraphs in list items.
+    fn tighten(&mut self) {
+        if !self.lists.is_empty() {
+            while self.out.len() > self.list_start && self.out.ends_with("\n\n") {
+                self.out.pop();
+            }
+        }
+    }
+
+    fn inline(&mut self, text: &str) {
+        let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            if text.chars().next().is_some_and(char::is_whitespace) && !self.out.ends_with([' ', '\n']) && !self.out.is_empty() {
+                self.out.push(' ');
+            }
+            return;
+        }
+        let needs_space = text.starts_with(char::is_whitespace) && !self.out.ends_with([' ', '\n', '(', '[']);
+        if needs_space && !self.out.is_empty() {
+            self.out.push(' ');
+        }
+        self.push(&collapsed);
+        if text.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+    }
+
+    fn children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.inline(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        if SKIPPED.contains(&name) {
+            return;
+        }
+        let in_body = element.parent().and_then(ElementRef::wrap).is_some_and(|p| p.value().name() == "body");
+        if in_body && PAGE_CHROME.contains(&name) {
+            return;
+        }
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                let heading = inline_text(element);
+                if !heading.is_empty() {
+                    self.blank_line();
+                    self.push(&format!("{} {}", "#".repeat(level), heading));
+                    self.blank_line();
+                }
+            }
+            "br" => self.push("\n"),
+            "ul" | "ol" => {
+                let nested = !self.lists.is_empty();
+                if nested {
+                    self.tighten();
+                    self.newline();
+                } else {
+                    self.blank_line();
+                    self.list_start = self.out.len();
+                }
+                self.lists.push(if name == "ol" { Some(1) } else { None });
+                self.children(element);
+                self.lists.pop();
+                if nested {
+                    self.newline();
+                } else {
+                    self.blank_line();
+                }
+            }
+            "li" => {
+                self.tighten();
+                self.newline();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "-".to_string(),
+                };
+                self.out.push_str(&format!("{}{} ", indent, marker));
+                self.item_start = true;
+                self.children(element);
+                self.newline();
+            }
+            "table" => {
+                self.blank_line();
+                self.push(&table_to_text(element));
+                self.blank_line();
+            }
+            "pre" => {
+                self.blank_line();
+                self.push(&format!("```\n{}\n```", element.text().collect::<String>().trim_end()));
+                self.blank_line();
+            }
+            "a" => {
+                let label = inline_text(element);
+                match element.value().attr("href").filter(|h| h.starts_with("http") || h.starts_with("mailto:")) {
+                    Some(href) if !label.is_empty() && label != href => self.inline(&format!(" [{}]({})", label, href)),
+                    _ => self.children(element),
+                }
+            }
+            "img" => {
+                if let Some(alt) = element.value().attr("alt").filter(|a| !a.trim().is_empty()) {
+                    self.inline(&format!(" [{}]", alt.trim()));
+                }
+            }
+            _ if BLOCKS.contains(&name) => {
+                self.blank_line();
+                self.children(element);
+                self.blank_line();
+            }
+            _ => self.children(element),
+        }
+    }
+}
+
+/// The text of an element on a single line.
+fn inline_text(element: ElementRef) -> String {
+    let mut writer = Writer::default();
+    writer.children(element);
+    writer.out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn table_to_text(table: ElementRef) -> String {
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let rows: Vec<Vec<String>> = table.select(&row_selector)
+        .map(|row| row.select(&cell_selector).map(|cell| inline_text(cell).replace('|', "\\|")).collect::<Vec<_>>())
+        .filter(|cells| cells.iter().any(|c| !c.is_empty()))
+        .collect();
+
+    let mut out = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+        if i == 0 {
+            out.push_str(&format!("|{}\n", " --- |".repeat(row.len())));
+        }
+    }
+    out
+}
+
+/// Converts an HTML document to text. When the page marks its main content
+/// (`<main>`, `<article>`), only that part is used.
+pub fn html_to_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let main_selector = Selector::parse("main, article, [role=main]").unwrap();
+    let mut writer = Writer::default();
+
+    let mains: Vec<ElementRef> = document.select(&main_selector).collect();
+    if mains.is_empty() {
+        writer.element(document.root_element());
+    } else {
+        // Skip nested matches (an <article> inside <main>) so nothing is written twice.
+        for main in &mains {
+            let nested = main.ancestors().filter_map(ElementRef::wrap).any(|a| mains.contains(&a));
+            if !nested {
+                writer.element(*main);
+                writer.blank_line();
+            }
+        }
+    }
+
+    writer.out.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
@@ -0,0 +1,446 @@
+mod epub;
+mod html;
+mod office;
+mod pdf;
+mod rtf;
+mod spreadsheet;
+mod text;
+
+pub use html::html_to_text;
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+pub const MIME_PDF: &str = "application/pdf";
+pub const MIME_DOCX: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+pub const MIME_XLSX: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+pub const MIME_PPTX: &str = "application/vnd.openxmlformats-officedocument.presentationml.presentation";
+pub const MIME_XLS: &str = "application/vnd.ms-excel";
+pub const MIME_DOC: &str = "application/msword";
+pub const MIME_PPT: &str = "application/vnd.ms-powerpoint";
+pub const MIME_ODT: &str = "application/vnd.oasis.opendocument.text";
+pub const MIME_ODS: &str = "application/vnd.oasis.opendocument.spreadsheet";
+pub const MIME_ODP: &str = "application/vnd.oasis.opendocument.presentation";
+pub const MIME_EPUB: &str = "application/epub+zip";
+pub const MIME_RTF: &str = "application/rtf";
+pub const MIME_HTML: &str = "text/html";
+pub const MIME_MARKDOWN: &str = "text/markdown";
+pub const MIME_CSV: &str = "text/csv";
+pub const MIME_TEXT: &str = "text/plain";
+pub const MIME_PNG: &str = "image/png";
+pub const MIME_JPEG: &str = "image/jpeg";
+pub const MIME_WEBP: &str = "image/webp";
+pub const MIME_ZIP: &str = "application/zip";
+pub const MIME_BINARY: &str = "application/octet-stream";
+
+/// Turns the bytes of one kind of document into plain text for the model.
+pub trait Extractor: Send + Sync {
+    /// Human-readable format name used in error messages.
+    fn name(&self) -> &'static str;
+    fn handles(&self, mime: &str) -> bool;
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure>;
+}
+
+/// Why an extractor produced no text.
+pub enum Failure {
+    Error(String),
+    /// Password protected and cannot be opened without the password.
+    Encrypted,
+    /// There is no text layer, e.g. a scanned document that would need OCR.
+    NoText,
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Error(message)
+    }
+}
+
+impl From<&str> for Failure {
+    fn from(message: &str) -> Self {
+        Failure::Error(message.to_string())
+    }
+}
+
+pub enum ExtractError {
+    Io(String),
+    Binary,
+    TooLarge { size: u64, limit: u64 },
+    Unsupported(String),
+    Encrypted,
+    NoText,
+    Failed { format: &'static str, message: String },
+}
+
+/// What to do with a text file larger than the upload limit. Other formats
+/// cannot be cut, they are always rejected.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum TruncationStrategy {
+    /// Keep the beginning and the end, e.g. a log's header and latest lines.
+    #[default]
+    HeadTail,
+    Head,
+    Reject,
+}
+
+/// The bytes of an uploaded file, or only its beginning and end when a text
+/// file exceeds the size limit.
+pub enum Upload {
+    Full(Vec<u8>),
+    Truncated { head: Vec<u8>, tail: Vec<u8>, omitted: u64 },
+}
+
+fn read_range(file: &mut File, start: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(len as usize);
+    file.seek(SeekFrom::Start(start))?;
+    file.take(len).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reads a file without ever holding more than `max_bytes` of it in memory.
+pub fn read_upload(path: &Path, max_bytes: u64, strategy: TruncationStrategy) -> Result<Upload, ExtractError> {
+    let io_error = |e: std::io::Error| ExtractError::Io(e.to_string());
+    let mut file = File::open(path).map_err(io_error)?;
+    let size = file.metadata().map_err(io_error)?.len();
+    if size <= max_bytes {
+        return read_range(&mut file, 0, size).map(Upload::Full).map_err(io_error);
+    }
+
+    let too_large = ExtractError::TooLarge { size, limit: max_bytes };
+    let probe = read_range(&mut file, 0, max_bytes.min(8 * 1024)).map_err(io_error)?;
+    if strategy == TruncationStrategy::Reject || !sniff(path, &probe).starts_with("text/") {
+        return Err(too_large);
+    }
+
+    let (head_len, tail_len) = match strategy {
+        TruncationStrategy::Head => (max_bytes, 0),
+        _ => (max_bytes / 2, max_bytes / 2),
+    };
+    let head = read_range(&mut file, 0, head_len).map_err(io_error)?;
+    let tail = read_range(&mut file, size - tail_len, tail_len).map_err(io_error)?;
+    Ok(Upload::Truncated { head, tail, omitted: size - head_len - tail_len })
+}
+
+/// The extractors `upload_file` can use, looked up by sniffed MIME type.
+/// Later registrations take precedence, so a plugin can override a built-in.
+pub struct ExtractorRegistry {
+    extractors: Vec<Box<dyn Extractor>>,
+}
+
+impl Default for ExtractorRegistry {
+    fn default() -> Self {
+        let mut registry = Self { extractors: Vec::new() };
+        registry.register(Box::new(text::PlainText));
+        registry.register(Box::new(html::HtmlPage));
+        registry.register(Box::new(pdf::Pdf));
+        registry.register(Box::new(office::Docx));
+        registry.register(Box::new(office::Pptx));
+        registry.register(Box::new(office::OpenDocument));
+        registry.register(Box::new(spreadsheet::Spreadsheet));
+        registry.register(Box::new(epub::Epub));
+        registry.register(Box::new(rtf::Rtf));
+        registry
+    }
+}
+
+impl ExtractorRegistry {
+    pub fn register(&mut self, extractor: Box<dyn Extractor>) {
+        self.extractors.push(extractor);
+    }
+
+    pub fn find(&self, mime: &str) -> Option<&dyn Extractor> {
+        self.extractors.iter().rev().find(|e| e.handles(mime)).map(|e| e.as_ref())
+    }
+
+    /// Sniffs the content type and extracts the text. Returns the MIME type too.
+    pub fn extract(&self, path: &Path, bytes: &[u8]) -> Result<(String, String), ExtractError> {
+        let mime = sniff(path, bytes);
+        if mime == MIME_BINARY {
+            return Err(ExtractError::Binary);
+        }
+        let extractor = self.find(mime).ok_or_else(|| ExtractError::Unsupported(mime.to_string()))?;
+        Ok((mime.to_string(), run(extractor, bytes)?))
+    }
+
+    /// Like [`Self::extract`], but a truncated text file is extracted
+    /// piecewise, head and tail separately, with a marker where the middle was
+    /// left out.
+    pub fn extract_upload(&self, path: &Path, upload: &Upload) -> Result<(String, String), ExtractError> {
+        let (head, tail, omitted) = match upload {
+            Upload::Full(bytes) => return self.extract(path, bytes),
+            Upload::Truncated { head, tail, omitted } => (head, tail, omitted),
+        };
+
+        // Cut at line boundaries so no line (or character) is split in half.
+        let head = head.iter().rposition(|&b| b == b'\n').map_or(head.as_slice(), |i| &head[..i]);
+        let tail = tail.iter().position(|&b| b == b'\n').map_or(tail.as_slice(), |i| &tail[i + 1..]);
+        let mime = sniff(path, head);
+        let extractor = self.find(mime).ok_or_else(|| ExtractError::Unsupported(mime.to_string()))?;
+        // The tail has no BOM or markup to detect from, so both halves are
+        // decoded with the head's encoding and handed on as UTF-8.
+        let encoding = text::detect_encoding(head);
+        let mut text = run(extractor, encoding.decode(head).0.as_bytes())?;
+        text.push_str(&format!("\n\n[... {} bytes omitted ...]\n\n", omitted));
+        text.push_str(&run(extractor, encoding.decode(tail).0.as_bytes())?);
+        Ok((mime.to_string(), text))
+    }
+}
+
+fn run(extractor: &dyn Extractor, bytes: &[u8]) -> Result<String, ExtractError> {
+    extractor.extract(bytes).map_err(|failure| match failure {
+        Failure::Error(message) => ExtractError::Failed { format: extractor.name(), message },
+        Failure::Encrypted => ExtractError::Encrypted,
+        Failure::NoText => ExtractError::NoText,
+    })
+}
+
+/// Images are not extracted to text; vision-capable models read them directly.
+/// WebP is recognised but not among them: the vision projector only decodes
+/// PNG and JPEG, so such uploads are reported as unsupported.
+pub fn is_image(mime: &str) -> bool {
+    mime == MIME_PNG || mime == MIME_JPEG
+}
+
+/// Determines the MIME type from the content. The extension is only used to
+/// tell apart text formats that look the same (Markdown, CSV, HTML).
+pub fn sniff(path: &Path, bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"%PDF") {
+        return MIME_PDF;
+    }
+    if bytes.starts_with(b"{\\rtf") {
+        return MIME_RTF;
+    }
+    if bytes.starts_with(&[0xD0, 0xCF, 0x11, 0xE0]) {
+        return sniff_ole(path, bytes);
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        return sniff_zip(bytes);
+    }
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return MIME_PNG;
+    }
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return MIME_JPEG;
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return MIME_WEBP;
+    }
+
+    if !text::looks_like_text(bytes) {
+        return MIME_BINARY;
+    }
+
+    let extension = path.extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).trim_start_matches('\u{FEFF}').trim_start().to_lowercase();
+    if head.starts_with("<!doctype html") || head.starts_with("<html") || matches!(extension.as_str(), "html" | "htm") {
+        return MIME_HTML;
+    }
+    match extension.as_str() {
+        "md" | "markdown" => MIME_MARKDOWN,
+        "csv" | "tsv" => MIME_CSV,
+        _ => MIME_TEXT,
+    }
+}
+
+/// Legacy Word, Excel and PowerPoint files are all OLE compound files; the
+/// stream names in their directory (UTF-16) tell them apart, and the extension
+/// when the directory lies beyond the bytes we have.
+fn sniff_ole(path: &Path, bytes: &[u8]) -> &'static str {
+    // Directory entry names are NUL-terminated, which keeps document text
+    // that happens to contain the same word from matching.
+    let has_stream = |name: &str| {
+        let utf16: Vec<u8> = name.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect();
+        bytes.windows(utf16.len()).any(|w| w == utf16.as_slice())
+    };
+    if has_stream("WordDocument") {
+        return MIME_DOC;
+    }
+    if has_stream("PowerPoint Document") {
+        return MIME_PPT;
+    }
+    if has_stream("Workbook") || has_stream("Book") {
+        return MIME_XLS;
+    }
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "doc" | "dot" => MIME_DOC,
+        "ppt" | "pps" | "pot" => MIME_PPT,
+        _ => MIME_XLS,
+    }
+}
+
+/// Office, OpenDocument and EPUB files are all zip containers; their entries
+/// tell them apart.
+fn sniff_zip(bytes: &[u8]) -> &'static str {
+    let Ok(mut archive) = zip::ZipArchive::new(Cursor::new(bytes)) else {
+        return MIME_BINARY;
+    };
+
+    if let Ok(mut entry) = archive.by_name("mimetype") {
+        let mut declared = String::new();
+        if entry.read_to_string(&mut declared).is_ok() {
+            match declared.trim() {
+                MIME_EPUB => return MIME_EPUB,
+                MIME_ODT => return MIME_ODT,
+                MIME_ODS => return MIME_ODS,
+                MIME_ODP => return MIME_ODP,
+                _ => {}
+            }
+        }
+    }
+
+    let has = |name: &str| archive.file_names().any(|n| n == name);
+    if has("word/document.xml") {
+        MIME_DOCX
+    } else if has("xl/workbook.xml") {
+        MIME_XLSX
+    } else if has("ppt/presentation.xml") {
+        MIME_PPTX
+    } else {
+        MIME_ZIP
+    }
+}
+
+pub(crate) fn read_zip_entry(bytes: &[u8], name: &str) -> Result<String, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let mut entry = archive.by_name(name).map_err(|e| format!("{}: {}", name, e))?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).map_err(|e| e.to_string())?;
+    Ok(content)
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let value = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(value)
+        }
+    }
+}
+
+/// Strips the markup from an XML document part. A line break is inserted
+/// after every element named in `breaks` (e.g. `a:p`, `text:p`) and a tab for
+/// `tabs`, so paragraphs and table cells stay apart.
+pub(crate) fn xml_to_text(xml: &str, breaks: &[&str], tabs: &[&str]) -> String {
+    let mut out = String::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(['<', '&']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with('&') {
+            match rest.find(';').filter(|&end| end <= 10) {
+                Some(end) => {
+                    match decode_entity(&rest[1..end]) {
+                        Some(c) => out.push(c),
+                        None => out.push_str(&rest[..=end]),
+                    }
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &rest[1..];
+                }
+            }
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag.trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        if (closing || self_closing) && breaks.contains(&name) {
+            out.push('\n');
+        } else if (closing || self_closing) && tabs.contains(&name) {
+            out.push('\t');
+        }
+    }
+    out.push_str(rest);
+
+    out.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_with(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn ole_with(stream: &str) -> Vec<u8> {
+        let mut bytes = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        bytes.extend(stream.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+        bytes
+    }
+
+    #[test]
+    fn sniffs_by_magic_bytes() {
+        let path = Path::new("upload.bin");
+        assert_eq!(sniff(path, b"%PDF-1.7\n"), MIME_PDF);
+        assert_eq!(sniff(path, b"{\\rtf1\\ansi"), MIME_RTF);
+        assert_eq!(sniff(path, b"\x89PNG\r\n\x1a\n...."), MIME_PNG);
+        assert_eq!(sniff(path, &[0xFF, 0xD8, 0xFF, 0xE0]), MIME_JPEG);
+        assert_eq!(sniff(path, b"RIFF\0\0\0\0WEBPVP8 "), MIME_WEBP);
+        assert_eq!(sniff(path, &[0, 1, 2, 3, 0, 0]), MIME_BINARY);
+    }
+
+    #[test]
+    fn sniffs_zip_containers() {
+        let path = Path::new("upload.zip");
+        assert_eq!(sniff(path, &zip_with(&[("word/document.xml", "")])), MIME_DOCX);
+        assert_eq!(sniff(path, &zip_with(&[("xl/workbook.xml", "")])), MIME_XLSX);
+        assert_eq!(sniff(path, &zip_with(&[("ppt/presentation.xml", "")])), MIME_PPTX);
+        assert_eq!(sniff(path, &zip_with(&[("mimetype", MIME_EPUB)])), MIME_EPUB);
+        assert_eq!(sniff(path, &zip_with(&[("mimetype", MIME_ODT)])), MIME_ODT);
+        assert_eq!(sniff(path, &zip_with(&[("readme.txt", "hi")])), MIME_ZIP);
+    }
+
+    #[test]
+    fn sniffs_ole_streams_then_extension() {
+        assert_eq!(sniff(Path::new("a.xls"), &ole_with("WordDocument")), MIME_DOC);
+        assert_eq!(sniff(Path::new("a.bin"), &ole_with("PowerPoint Document")), MIME_PPT);
+        assert_eq!(sniff(Path::new("a.doc"), &ole_with("Workbook")), MIME_XLS);
+        assert_eq!(sniff(Path::new("a.ppt"), &ole_with("Other")), MIME_PPT);
+    }
+
+    #[test]
+    fn sniffs_text_by_content_and_extension() {
+        assert_eq!(sniff(Path::new("page.txt"), b"\xEF\xBB\xBF<!DOCTYPE html><html>"), MIME_HTML);
+        assert_eq!(sniff(Path::new("notes.md"), b"# Title"), MIME_MARKDOWN);
+        assert_eq!(sniff(Path::new("data.TSV"), b"a\tb"), MIME_CSV);
+        assert_eq!(sniff(Path::new("main.rs"), b"fn main() {}"), MIME_TEXT);
+    }
+}
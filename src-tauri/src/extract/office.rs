@@ -0,0 +1,74 @@
+use super::{read_zip_entry, xml_to_text, Extractor, Failure, MIME_DOCX, MIME_ODP, MIME_ODT, MIME_PPTX};
+use std::io::Cursor;
+
+pub struct Docx;
+
+impl Extractor for Docx {
+    fn name(&self) -> &'static str {
+        "Word"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_DOCX
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        let xml = read_zip_entry(bytes, "word/document.xml")?;
+        Ok(xml_to_text(&xml, &["w:p", "w:br", "w:tr"], &["w:tab", "w:tc"]))
+    }
+}
+
+/// PowerPoint slides in order, each under its own heading.
+pub struct Pptx;
+
+impl Extractor for Pptx {
+    fn name(&self) -> &'static str {
+        "PowerPoint"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_PPTX
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        let archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+        let mut slides: Vec<(u32, String)> = archive.file_names()
+            .filter_map(|name| {
+                let number = name.strip_prefix("ppt/slides/slide")?.strip_suffix(".xml")?.parse().ok()?;
+                Some((number, name.to_string()))
+            })
+            .collect();
+        slides.sort();
+
+        let mut out = String::new();
+        for (number, name) in slides {
+            let text = xml_to_text(&read_zip_entry(bytes, &name)?, &["a:p", "a:br", "a:tr"], &["a:tc"]);
+            if !text.is_empty() {
+                out.push_str(&format!("## Slide {}\n{}\n\n", number, text));
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// OpenDocument text documents and presentations (`content.xml`).
+pub struct OpenDocument;
+
+impl Extractor for OpenDocument {
+    fn name(&self) -> &'static str {
+        "OpenDocument"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_ODT || mime == MIME_ODP
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        let xml = read_zip_entry(bytes, "content.xml")?;
+        Ok(xml_to_text(
+            &xml,
+            &["text:p", "text:h", "text:line-break", "table:table-row", "draw:page"],
+            &["text:tab", "table:table-cell"],
+        ))
+    }
+}
@@ -0,0 +1,240 @@
+use super::{Extractor, Failure, MIME_PDF};
+use pdf_extract::{Document, MediaBox, OutputDev, OutputError, Transform};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// PDFs laid out from glyph positions: pages are separated by form feeds
+/// (`\x0c`), larger-font lines become Markdown headings and column-aligned
+/// runs of lines become tables.
+pub struct Pdf;
+
+/// Horizontal gap, in font sizes, that separates two table cells.
+const CELL_GAP: f64 = 2.0;
+/// Horizontal gap, in font sizes, that separates two words.
+const WORD_GAP: f64 = 0.15;
+/// How far, in font sizes, a cell's left or right edge may be from its
+/// column's to still count as aligned.
+const COLUMN_TOLERANCE: f64 = 1.0;
+/// Below this many characters per page on average the PDF is taken to be scanned.
+const MIN_CHARS_PER_PAGE: usize = 10;
+
+struct Glyph {
+    x: f64,
+    end: f64,
+    y: f64,
+    size: f64,
+    text: String,
+}
+
+#[derive(Default)]
+struct GlyphCollector {
+    glyphs: Vec<Glyph>,
+}
+
+impl OutputDev for GlyphCollector {
+    fn begin_page(&mut self, _page_num: u32, _media_box: &MediaBox, _art_box: Option<(f64, f64, f64, f64)>) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(&mut self, trm: &Transform, width: f64, _spacing: f64, font_size: f64, char: &str) -> Result<(), OutputError> {
+        let size = font_size * (trm.m11 * trm.m22 - trm.m12 * trm.m21).abs().sqrt();
+        if size > 0.0 && !char.trim().is_empty() {
+            self.glyphs.push(Glyph {
+                x: trm.m31,
+                end: trm.m31 + width * size,
+                y: trm.m32,
+                size,
+                text: char.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+struct Line {
+    y: f64,
+    size: f64,
+    cells: Vec<String>,
+    /// Left and right edge of each cell.
+    spans: Vec<(f64, f64)>,
+}
+
+/// Groups glyphs with the same baseline into lines, top to bottom, and splits
+/// each line into cells at wide horizontal gaps.
+fn layout_lines(mut glyphs: Vec<Glyph>) -> Vec<Line> {
+    glyphs.sort_by(|a, b| b.y.total_cmp(&a.y));
+    let mut rows: Vec<Vec<Glyph>> = Vec::new();
+    for glyph in glyphs {
+        match rows.last_mut() {
+            Some(row) if (row[0].y - glyph.y).abs() < row[0].size.min(glyph.size) * 0.5 => row.push(glyph),
+            _ => rows.push(vec![glyph]),
+        }
+    }
+
+    rows.into_iter()
+        .map(|mut row| {
+            row.sort_by(|a, b| a.x.total_cmp(&b.x));
+            let size = row.iter().map(|g| g.size).fold(0.0, f64::max);
+            let mut cells = vec![String::new()];
+            let mut spans = vec![(row[0].x, row[0].end)];
+            let mut last_end: Option<f64> = None;
+            for glyph in &row {
+                if let Some(end) = last_end {
+                    let gap = glyph.x - end;
+                    if gap > glyph.size * CELL_GAP {
+                        cells.push(String::new());
+                        spans.push((glyph.x, glyph.end));
+                    } else if gap > glyph.size * WORD_GAP {
+                        cells.last_mut().unwrap().push(' ');
+                    }
+                }
+                cells.last_mut().unwrap().push_str(&glyph.text);
+                let end = glyph.end.max(last_end.unwrap_or(f64::MIN));
+                spans.last_mut().unwrap().1 = end;
+                last_end = Some(end);
+            }
+            Line { y: row[0].y, size, cells, spans }
+        })
+        .collect()
+}
+
+/// The most common font size on the page, taken as the body text size.
+fn body_size(lines: &[Line]) -> f64 {
+    let mut sizes: Vec<(i64, usize)> = Vec::new();
+    for line in lines {
+        let key = (line.size * 2.0).round() as i64;
+        let weight = line.cells.iter().map(|c| c.len()).sum::<usize>();
+        match sizes.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, count)) => *count += weight,
+            None => sizes.push((key, weight)),
+        }
+    }
+    sizes.into_iter().max_by_key(|&(_, count)| count).map_or(0.0, |(key, _)| key as f64 / 2.0)
+}
+
+/// Places the cells of `line` in the given columns, or `None` when a cell
+/// lines up with no column, or two cells with the same one. Left-aligned
+/// cells share a left edge, right-aligned (numeric) ones a right edge.
+fn align_row(line: &Line, columns: &[(f64, f64)], tolerance: f64) -> Option<Vec<String>> {
+    if line.cells.len() < 2 {
+        return None;
+    }
+    let mut row = vec![String::new(); columns.len()];
+    let mut used = vec![false; columns.len()];
+    for (cell, &(start, end)) in line.cells.iter().zip(&line.spans) {
+        let column = columns.iter().position(|&(column_start, column_end)| {
+            (start - column_start).abs() <= tolerance || (end - column_end).abs() <= tolerance
+        })?;
+        if std::mem::replace(&mut used[column], true) {
+            return None;
+        }
+        row[column] = cell.trim().replace('|', "\\|");
+    }
+    Some(row)
+}
+
+fn push_table(out: &mut String, rows: &[Vec<String>]) {
+    let width = rows.first().map_or(0, Vec::len);
+    for (i, cells) in rows.iter().enumerate() {
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        if i == 0 {
+            out.push_str(&format!("|{}\n", " --- |".repeat(width)));
+        }
+    }
+    out.push('\n');
+}
+
+fn render_page(glyphs: Vec<Glyph>) -> String {
+    let lines = layout_lines(glyphs);
+    let body = body_size(&lines);
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = &lines[i];
+        if i > 0 && lines[i - 1].y - line.y > lines[i - 1].size.max(line.size) * 1.8 && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+
+        // Two or more consecutive multi-cell lines whose cells line up with
+        // the first one's columns form a table.
+        let tolerance = line.size * COLUMN_TOLERANCE;
+        let rows: Vec<Vec<String>> = lines[i..].iter().map_while(|l| align_row(l, &line.spans, tolerance)).collect();
+        if rows.len() >= 2 {
+            if !out.is_empty() && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+            push_table(&mut out, &rows);
+            i += rows.len();
+            continue;
+        }
+
+        let text = line.cells.join("  ");
+        if body > 0.0 && line.size >= body * 1.2 && text.chars().count() <= 120 {
+            let level = if line.size >= body * 1.6 { "#" } else { "##" };
+            if !out.is_empty() && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+            out.push_str(&format!("{} {}\n\n", level, text.trim()));
+        } else {
+            out.push_str(text.trim_end());
+            out.push('\n');
+        }
+        i += 1;
+    }
+    out.trim_end().to_string()
+}
+
+impl Extractor for Pdf {
+    fn name(&self) -> &'static str {
+        "PDF"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_PDF
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        let mut doc = Document::load_mem(bytes).map_err(|e| e.to_string())?;
+        if doc.is_encrypted() && doc.decrypt("").is_err() {
+            return Err(Failure::Encrypted);
+        }
+
+        let page_numbers: Vec<u32> = doc.get_pages().keys().copied().collect();
+        let mut pages = Vec::with_capacity(page_numbers.len());
+        for &page_num in &page_numbers {
+            // A broken page should not cost the rest of the document; the PDF
+            // parser panics on some malformed content streams.
+            let page = catch_unwind(AssertUnwindSafe(|| {
+                let mut collector = GlyphCollector::default();
+                let _ = pdf_extract::output_doc_page(&doc, &mut collector, page_num);
+                render_page(collector.glyphs)
+            }));
+            pages.push(page.unwrap_or_else(|_| {
+                println!(">>> PDF: a(z) {}. oldal feldolgozása összeomlott, kihagyva", page_num);
+                String::new()
+            }));
+        }
+
+        let chars = pages.iter().flat_map(|p| p.chars()).filter(|c| !c.is_whitespace()).count();
+        if chars < MIN_CHARS_PER_PAGE * page_numbers.len().max(1) {
+            return Err(Failure::NoText);
+        }
+        Ok(pages.join("\n\x0c"))
+    }
+}
@@ -0,0 +1,155 @@
+use super::{Extractor, Failure, MIME_RTF};
+use encoding_rs::{Encoding, WINDOWS_1252};
+
+/// Rich Text Format. Formatting is dropped; paragraphs, tabs and table cells
+/// are kept, and `\'hh` bytes are decoded with the document's code page.
+pub struct Rtf;
+
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl", "colortbl", "stylesheet", "info", "pict", "object", "header", "footer",
+    "headerl", "headerr", "footerl", "footerr", "themedata", "datastore", "xmlnstbl",
+];
+
+#[derive(Clone, Copy)]
+struct Group {
+    skip: bool,
+    unicode_skip: usize,
+}
+
+struct Output {
+    text: String,
+    pending: Vec<u8>,
+    encoding: &'static Encoding,
+}
+
+impl Output {
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            let (decoded, _, _) = self.encoding.decode(&self.pending);
+            self.text.push_str(&decoded);
+            self.pending.clear();
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        self.flush();
+        self.text.push(c);
+    }
+}
+
+impl Extractor for Rtf {
+    fn name(&self) -> &'static str {
+        "RTF"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_RTF
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        Ok(rtf_to_text(bytes))
+    }
+}
+
+fn rtf_to_text(bytes: &[u8]) -> String {
+    let mut out = Output { text: String::new(), pending: Vec::new(), encoding: WINDOWS_1252 };
+    let mut stack: Vec<Group> = Vec::new();
+    let mut group = Group { skip: false, unicode_skip: 1 };
+    // Fallback characters still to drop after a `\uN` escape.
+    let mut to_skip = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        i += 1;
+        match b {
+            b'{' => {
+                stack.push(group);
+                to_skip = 0;
+            }
+            b'}' => {
+                group = stack.pop().unwrap_or(group);
+                to_skip = 0;
+            }
+            b'\r' | b'\n' => {}
+            b'\\' => {
+                let Some(&next) = bytes.get(i) else { break };
+                if next.is_ascii_alphabetic() {
+                    let start = i;
+                    while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    let word = std::str::from_utf8(&bytes[start..i]).unwrap_or("");
+                    let num_start = i;
+                    if i < bytes.len() && bytes[i] == b'-' {
+                        i += 1;
+                    }
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                    let param: Option<i32> = std::str::from_utf8(&bytes[num_start..i]).ok().and_then(|s| s.parse().ok());
+                    if i < bytes.len() && bytes[i] == b' ' {
+                        i += 1;
+                    }
+
+                    if SKIPPED_DESTINATIONS.contains(&word) {
+                        group.skip = true;
+                        continue;
+                    }
+                    match word {
+                        "ansicpg" => {
+                            if let Some(encoding) = param.and_then(|cp| Encoding::for_label(format!("windows-{}", cp).as_bytes())) {
+                                out.encoding = encoding;
+                            }
+                        }
+                        "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                        "u" if !group.skip => {
+                            let code = param.unwrap_or(0);
+                            let code = if code < 0 { code + 65536 } else { code } as u32;
+                            if let Some(c) = char::from_u32(code) {
+                                out.push(c);
+                            }
+                            to_skip = group.unicode_skip;
+                        }
+                        "par" | "line" | "row" | "page" | "sect" if !group.skip => out.push('\n'),
+                        "tab" | "cell" if !group.skip => out.push('\t'),
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                i += 1;
+                match next {
+                    b'*' => group.skip = true,
+                    b'\'' => {
+                        let hex = bytes.get(i..i + 2).and_then(|h| std::str::from_utf8(h).ok());
+                        if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                            i += 2;
+                            if to_skip > 0 {
+                                to_skip -= 1;
+                            } else if !group.skip {
+                                out.pending.push(byte);
+                            }
+                        }
+                    }
+                    b'~' if !group.skip => out.push(' '),
+                    b'_' if !group.skip => out.push('-'),
+                    b'\\' | b'{' | b'}' if !group.skip => out.push(next as char),
+                    b'\r' | b'\n' if !group.skip => out.push('\n'),
+                    _ => {}
+                }
+            }
+            _ if to_skip > 0 => to_skip -= 1,
+            _ if group.skip => {}
+            _ => out.pending.push(b),
+        }
+    }
+    out.flush();
+
+    out.text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
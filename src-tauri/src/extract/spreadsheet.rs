@@ -0,0 +1,46 @@
+use super::{Extractor, Failure, MIME_ODS, MIME_XLS, MIME_XLSX};
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use std::io::Cursor;
+
+/// Excel and OpenDocument spreadsheets, every sheet rendered as a Markdown table.
+pub struct Spreadsheet;
+
+fn cell_text(cell: &Data) -> String {
+    cell.to_string().replace('|', "\\|").replace('\n', " ")
+}
+
+impl Extractor for Spreadsheet {
+    fn name(&self) -> &'static str {
+        "spreadsheet"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime == MIME_XLSX || mime == MIME_XLS || mime == MIME_ODS
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        let mut workbook = open_workbook_auto_from_rs(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+        let mut out = String::new();
+
+        for sheet in workbook.sheet_names() {
+            let range = workbook.worksheet_range(&sheet).map_err(|e| e.to_string())?;
+            let rows: Vec<Vec<String>> = range.rows()
+                .filter(|row| row.iter().any(|c| !matches!(c, Data::Empty)))
+                .map(|row| row.iter().map(cell_text).collect())
+                .collect();
+            if rows.is_empty() {
+                continue;
+            }
+
+            out.push_str(&format!("## {}\n\n", sheet));
+            for (i, row) in rows.iter().enumerate() {
+                out.push_str(&format!("| {} |\n", row.join(" | ")));
+                if i == 0 {
+                    out.push_str(&format!("|{}\n", " --- |".repeat(row.len())));
+                }
+            }
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
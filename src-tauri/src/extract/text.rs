@@ -0,0 +1,123 @@
+use super::{Extractor, Failure};
+use encoding_rs::{Encoding, ISO_8859_2, UTF_8, WINDOWS_1250, WINDOWS_1252};
+
+/// Any text file: source code, configs, Markdown, CSV, HTML. UTF-8 and the
+/// legacy single-byte code pages (Windows-1250, Latin-2, Windows-1252) are
+/// recognised, see [`detect_encoding`].
+pub struct PlainText;
+
+impl Extractor for PlainText {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn handles(&self, mime: &str) -> bool {
+        mime.starts_with("text/")
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<String, Failure> {
+        Ok(decode(bytes))
+    }
+}
+
+/// Letters whose presence makes a legacy decoding plausible, mostly Hungarian
+/// and other Central European ones.
+const EXPECTED_LETTERS: &str = "áéíóöőúüűÁÉÍÓÖŐÚÜŰčćďěľĺňřšťžźżąęłńśČĆĎĚĽĹŇŘŠŤŽŹŻĄĘŁŃŚäàâçèêëîïôùûÄÀÂÇÈÊËÎÏÔÙÛß";
+
+/// Valid UTF-8, allowing a multi-byte sequence cut off at the very end (the
+/// head of a truncated file).
+fn is_utf8(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn legacy_score(text: &str) -> i64 {
+    text.chars()
+        .map(|c| {
+            if EXPECTED_LETTERS.contains(c) {
+                1
+            } else if (c.is_control() && !c.is_whitespace()) || c == '\u{FFFD}' {
+                -5
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// Picks the encoding of a text file: a byte order mark wins, then UTF-8 if
+/// the bytes are valid, otherwise the legacy code page under which the text
+/// reads most like natural language.
+pub fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if is_utf8(bytes) {
+        return UTF_8;
+    }
+
+    let sample = &bytes[..bytes.len().min(64 * 1024)];
+    // On a tie `max_by_key` keeps the last candidate, so Windows-1250 is preferred.
+    [WINDOWS_1252, ISO_8859_2, WINDOWS_1250]
+        .into_iter()
+        .max_by_key(|encoding| {
+            let (text, _) = encoding.decode_without_bom_handling(sample);
+            legacy_score(&text)
+        })
+        .unwrap_or(WINDOWS_1250)
+}
+
+pub fn decode(bytes: &[u8]) -> String {
+    let (text, _, _) = detect_encoding(bytes).decode(bytes);
+    text.into_owned()
+}
+
+/// True when the bytes are text in some encoding. Binary files contain NUL
+/// bytes (unless they are UTF-16 with a BOM) or many other control characters.
+pub fn looks_like_text(bytes: &[u8]) -> bool {
+    if matches!(Encoding::for_bom(bytes), Some((encoding, _)) if encoding != UTF_8) {
+        return true;
+    }
+    let sample = &bytes[..bytes.len().min(8 * 1024)];
+    if sample.contains(&0) {
+        return false;
+    }
+    let controls = sample.iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+        .count();
+    controls * 100 <= sample.len() * 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::UTF_16LE;
+
+    #[test]
+    fn bom_and_utf8() {
+        assert_eq!(detect_encoding(b"\xFF\xFEh\0i\0"), UTF_16LE);
+        assert_eq!(detect_encoding("árvíztűrő tükörfúrógép".as_bytes()), UTF_8);
+        // A multi-byte character cut off at the end of a truncated head.
+        assert_eq!(detect_encoding(&"tűrő".as_bytes()[..6]), UTF_8);
+    }
+
+    #[test]
+    fn legacy_code_pages() {
+        let (hungarian, _, _) = WINDOWS_1250.encode("Árvíztűrő tükörfúrógép, őszi szél");
+        assert_eq!(detect_encoding(&hungarian), WINDOWS_1250);
+        assert_eq!(decode(&hungarian), "Árvíztűrő tükörfúrógép, őszi szél");
+
+        let (french, _, _) = WINDOWS_1252.encode("Très café, à bientôt, garçon");
+        assert_eq!(detect_encoding(&french), WINDOWS_1252);
+    }
+
+    #[test]
+    fn text_and_binary() {
+        assert!(looks_like_text(b"plain text\r\n\twith tabs"));
+        assert!(looks_like_text(b"\xFF\xFEh\0i\0"));
+        assert!(!looks_like_text(b"ELF\0\x02\x01"));
+        assert!(!looks_like_text(&[0x01; 64]));
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"GGUF";
+
+/// A metadata value from a GGUF header. Floats, booleans and arrays (the
+/// tokenizer vocabulary alone is megabytes of strings) are read past as `Other`.
+#[derive(Clone, Debug)]
+pub enum Value {
+    Uint(u64),
+    Int(i64),
+    Str(String),
+    Other,
+}
+
+/// The key/value metadata of a GGUF file, read without loading the model.
+#[derive(Clone, Debug, Default)]
+pub struct Metadata {
+    values: HashMap<String, Value>,
+}
+
+impl Metadata {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        read_header(&mut BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn str(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn uint(&self, key: &str) -> Option<u64> {
+        match self.get(key)? {
+            Value::Uint(n) => Some(*n),
+            Value::Int(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn architecture(&self) -> Option<&str> {
+        self.str("general.architecture")
+    }
+
+    /// An architecture-specific key, e.g. `arch_uint("block_count")` for `llama.block_count`.
+    pub fn arch_uint(&self, key: &str) -> Option<u64> {
+        self.uint(&format!("{}.{}", self.architecture()?, key))
+    }
+}
+
+fn read_header(r: &mut (impl Read + Seek)) -> io::Result<Metadata> {
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a GGUF file"));
+    }
+    let version = read_u32(r)?;
+    if version < 2 {
+        return Err(invalid("GGUF v1 is not supported"));
+    }
+    let _tensor_count = read_u64(r)?;
+    let kv_count = read_u64(r)?;
+
+    let mut values = HashMap::new();
+    for _ in 0..kv_count {
+        let key = read_string(r)?;
+        let kind = read_u32(r)?;
+        values.insert(key, read_value(r, kind)?);
+    }
+    Ok(Metadata { values })
+}
+
+fn read_value(r: &mut (impl Read + Seek), kind: u32) -> io::Result<Value> {
+    Ok(match kind {
+        0 => Value::Uint(read_n::<1>(r)?[0] as u64),
+        1 => Value::Int(read_n::<1>(r)?[0] as i8 as i64),
+        2 => Value::Uint(u16::from_le_bytes(read_n(r)?) as u64),
+        3 => Value::Int(i16::from_le_bytes(read_n(r)?) as i64),
+        4 => Value::Uint(read_u32(r)? as u64),
+        5 => Value::Int(i32::from_le_bytes(read_n(r)?) as i64),
+        6 => {
+            read_n::<4>(r)?;
+            Value::Other
+        }
+        7 => {
+            read_n::<1>(r)?;
+            Value::Other
+        }
+        8 => Value::Str(read_string(r)?),
+        9 => {
+            let item = read_u32(r)?;
+            let len = read_u64(r)?;
+            skip_array(r, item, len)?;
+            Value::Other
+        }
+        10 => Value::Uint(read_u64(r)?),
+        11 => Value::Int(i64::from_le_bytes(read_n(r)?)),
+        12 => {
+            read_n::<8>(r)?;
+            Value::Other
+        }
+        _ => return Err(invalid("unknown metadata type")),
+    })
+}
+
+fn skip_array(r: &mut (impl Read + Seek), item: u32, len: u64) -> io::Result<()> {
+    let width: i64 = match item {
+        0 | 1 | 7 => 1,
+        2 | 3 => 2,
+        4..=6 => 4,
+        10..=12 => 8,
+        8 => {
+            for _ in 0..len {
+                let n = read_u64(r)?;
+                r.seek(SeekFrom::Current(n as i64))?;
+            }
+            return Ok(());
+        }
+        9 => {
+            for _ in 0..len {
+                let (item, len) = (read_u32(r)?, read_u64(r)?);
+                skip_array(r, item, len)?;
+            }
+            return Ok(());
+        }
+        _ => return Err(invalid("unknown array type")),
+    };
+    r.seek(SeekFrom::Current(width * len as i64))?;
+    Ok(())
+}
+
+fn read_n<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    Ok(u32::from_le_bytes(read_n(r)?))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    Ok(u64::from_le_bytes(read_n(r)?))
+}
+
+fn read_string(r: &mut impl Read) -> io::Result<String> {
+    let len = read_u64(r)?;
+    // Keys and values are short; anything huge means a corrupt header.
+    if len > 64 * 1024 * 1024 {
+        return Err(invalid("string too long"));
+    }
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.extend((s.len() as u64).to_le_bytes());
+        out.extend(s.as_bytes());
+    }
+
+    fn key(out: &mut Vec<u8>, name: &str, kind: u32) {
+        string(out, name);
+        out.extend(kind.to_le_bytes());
+    }
+
+    /// A GGUF v3 header with one value of most metadata types.
+    fn header() -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(MAGIC);
+        out.extend(3u32.to_le_bytes());
+        out.extend(0u64.to_le_bytes());
+        out.extend(8u64.to_le_bytes());
+
+        key(&mut out, "general.architecture", 8);
+        string(&mut out, "llama");
+        key(&mut out, "llama.block_count", 4);
+        out.extend(32u32.to_le_bytes());
+        key(&mut out, "llama.context_length", 10);
+        out.extend(131072u64.to_le_bytes());
+        key(&mut out, "test.negative", 5);
+        out.extend((-7i32).to_le_bytes());
+        key(&mut out, "test.float", 6);
+        out.extend(0.5f32.to_le_bytes());
+        key(&mut out, "tokenizer.ggml.tokens", 9);
+        out.extend(8u32.to_le_bytes());
+        out.extend(3u64.to_le_bytes());
+        for token in ["<s>", "</s>", "hello"] {
+            string(&mut out, token);
+        }
+        key(&mut out, "tokenizer.ggml.scores", 9);
+        out.extend(6u32.to_le_bytes());
+        out.extend(2u64.to_le_bytes());
+        out.extend([0u8; 8]);
+        key(&mut out, "general.name", 8);
+        string(&mut out, "Tiny");
+        out
+    }
+
+    #[test]
+    fn reads_metadata_past_arrays() {
+        let metadata = read_header(&mut Cursor::new(header())).unwrap();
+        assert_eq!(metadata.architecture(), Some("llama"));
+        assert_eq!(metadata.arch_uint("block_count"), Some(32));
+        assert_eq!(metadata.arch_uint("context_length"), Some(131072));
+        assert!(matches!(metadata.get("test.negative"), Some(Value::Int(-7))));
+        assert_eq!(metadata.uint("test.negative"), None);
+        assert!(matches!(metadata.get("test.float"), Some(Value::Other)));
+        assert!(matches!(metadata.get("tokenizer.ggml.tokens"), Some(Value::Other)));
+        assert_eq!(metadata.str("general.name"), Some("Tiny"));
+    }
+
+    #[test]
+    fn reads_header_from_file() {
+        let path = std::env::temp_dir().join(format!("mia-gguf-{}.gguf", std::process::id()));
+        std::fs::write(&path, header()).unwrap();
+        let metadata = Metadata::read(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(metadata.unwrap().architecture(), Some("llama"));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(read_header(&mut Cursor::new(b"PK\x03\x04 not a model".to_vec())).is_err());
+
+        let mut v1 = header();
+        v1[4..8].copy_from_slice(&1u32.to_le_bytes());
+        assert!(read_header(&mut Cursor::new(v1)).is_err());
+
+        let mut truncated = header();
+        truncated.truncate(40);
+        assert!(read_header(&mut Cursor::new(truncated)).is_err());
+    }
+}
@@ -0,0 +1,373 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Whitespace allowed between JSON tokens; bounded so the model cannot stall
+/// on endless indentation.
+const SPACE_RULE: &str = r#"| " " | "\n" [ \t]{0,20}"#;
+
+const PRIMITIVES: [(&str, &str); 8] = [
+    ("boolean", r#"("true" | "false") space"#),
+    ("null", r#""null" space"#),
+    ("integer", r#"("-"? ([0-9] | [1-9] [0-9]{0,15})) space"#),
+    ("number", r#"("-"? ([0-9] | [1-9] [0-9]{0,15})) ("." [0-9]+)? ([eE] [-+]? [0-9]+)? space"#),
+    ("char", r#"[^"\\\x7F\x00-\x1F] | [\\] (["\\bfnrt] | "u" [0-9a-fA-F]{4})"#),
+    ("string", r#""\"" char* "\"" space"#),
+    ("value", r#"object | array | string | number | boolean | null"#),
+    ("object", r#""{" space (string ":" space value ("," space string ":" space value)*)? "}" space"#),
+];
+
+const ARRAY_RULE: &str = r#""[" space (value ("," space value)*)? "]" space"#;
+
+/// A GBNF string literal matching `text` exactly.
+pub fn literal(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A grammar whose only sentences are the given words.
+pub fn one_of(words: &[String]) -> String {
+    let alternatives: Vec<String> = words.iter().map(|w| literal(w)).collect();
+    format!("root ::= {}\n", alternatives.join(" | "))
+}
+
+/// Converts a JSON schema to a GBNF grammar with a `root` rule. Supports
+/// `type` (also as a list), `properties` / `required`, `items` with
+/// `minItems` / `maxItems`, string `minLength` / `maxLength`, `enum`,
+/// `const`, `anyOf` / `oneOf` and local `$ref`s (`#/$defs/...`,
+/// `#/definitions/...`). Other keywords, such as `pattern` or `format`, are
+/// ignored. Object properties are generated in the schema's key order.
+pub fn json_schema_to_gbnf(schema: &Value) -> Result<String, String> {
+    let mut converter = Converter { root: schema, rules: BTreeMap::new() };
+    let root = converter.visit(schema, "root")?;
+    if root != "root" {
+        converter.rules.insert("root".into(), root);
+    }
+    converter.rules.insert("space".into(), SPACE_RULE.into());
+
+    let mut out = String::new();
+    for (name, body) in &converter.rules {
+        out.push_str(&format!("{} ::= {}\n", name, body));
+    }
+    Ok(out)
+}
+
+struct Converter<'a> {
+    root: &'a Value,
+    rules: BTreeMap<String, String>,
+}
+
+impl Converter<'_> {
+    /// Adds the rule for `schema` and returns what refers to it: its rule
+    /// name, or a primitive's name.
+    fn visit(&mut self, schema: &Value, name: &str) -> Result<String, String> {
+        let name = rule_name(name);
+        let body = match schema {
+            Value::Bool(true) => return Ok(self.primitive("value")),
+            Value::Object(schema) => self.body(schema, &name)?,
+            _ => return Err(format!("Unsupported schema at {}: {}", name, schema)),
+        };
+        match body {
+            Body::Rule(body) => {
+                self.rules.insert(name.clone(), body);
+                Ok(name)
+            }
+            Body::Primitive(primitive) => Ok(self.primitive(primitive)),
+        }
+    }
+
+    fn body(&mut self, schema: &Map<String, Value>, name: &str) -> Result<Body, String> {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            return Ok(Body::Rule(self.reference(reference)?));
+        }
+        if let Some(value) = schema.get("const") {
+            return Ok(Body::Rule(format!("{} space", json_literal(value))));
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            let alternatives: Vec<String> = values.iter().map(json_literal).collect();
+            return Ok(Body::Rule(format!("({}) space", alternatives.join(" | "))));
+        }
+        if let Some(options) = schema.get("anyOf").or_else(|| schema.get("oneOf")).and_then(Value::as_array) {
+            let alternatives = options
+                .iter()
+                .enumerate()
+                .map(|(i, option)| self.visit(option, &format!("{}-{}", name, i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(Body::Rule(alternatives.join(" | ")));
+        }
+
+        match schema.get("type") {
+            Some(Value::Array(types)) => {
+                let alternatives = types
+                    .iter()
+                    .map(|t| {
+                        let mut single = schema.clone();
+                        single.insert("type".into(), t.clone());
+                        self.visit(&Value::Object(single), &format!("{}-{}", name, t.as_str().unwrap_or("type")))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Body::Rule(alternatives.join(" | ")))
+            }
+            Some(Value::String(t)) => match t.as_str() {
+                "object" => self.object(schema, name),
+                "array" => self.array(schema, name),
+                "string" => Ok(string(schema)),
+                "integer" | "number" | "boolean" | "null" => Ok(Body::Primitive(primitive_name(t))),
+                other => Err(format!("Unsupported type at {}: {}", name, other)),
+            },
+            // No type: properties imply an object, anything else is any value.
+            _ if schema.contains_key("properties") => self.object(schema, name),
+            _ => Ok(Body::Primitive("value")),
+        }
+    }
+
+    fn object(&mut self, schema: &Map<String, Value>, name: &str) -> Result<Body, String> {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object).filter(|p| !p.is_empty()) else {
+            return Ok(Body::Primitive("object"));
+        };
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|list| list.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut required_parts = Vec::new();
+        let mut optional_parts = Vec::new();
+        for (key, property) in properties {
+            let value = self.visit(property, &format!("{}-{}", name, key))?;
+            let part = format!("{} \":\" space {}", json_literal(&Value::String(key.clone())), value);
+            if required.contains(&key.as_str()) {
+                required_parts.push(part);
+            } else {
+                optional_parts.push(part);
+            }
+        }
+
+        let members = if required_parts.is_empty() {
+            // Whichever optional property comes first carries no comma.
+            let alternatives: Vec<String> = (0..optional_parts.len())
+                .map(|first| {
+                    let rest: String = optional_parts[first + 1..].iter().map(|p| format!(" (\",\" space {})?", p)).collect();
+                    format!("{}{}", optional_parts[first], rest)
+                })
+                .collect();
+            format!("({})?", alternatives.join(" | "))
+        } else {
+            let rest: String = optional_parts.iter().map(|p| format!(" (\",\" space {})?", p)).collect();
+            format!("{}{}", required_parts.join(" \",\" space "), rest)
+        };
+        Ok(Body::Rule(format!("\"{{\" space {} \"}}\" space", members)))
+    }
+
+    fn array(&mut self, schema: &Map<String, Value>, name: &str) -> Result<Body, String> {
+        let item = match schema.get("items") {
+            Some(items) => self.visit(items, &format!("{}-item", name))?,
+            None => self.primitive("value"),
+        };
+        let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+        let max = schema.get("maxItems").and_then(Value::as_u64);
+        if min == 0 && max.is_none() && schema.get("items").is_none() {
+            self.primitive("value");
+            return Ok(Body::Rule(ARRAY_RULE.into()));
+        }
+        let more = format!("(\",\" space {})", item);
+        let items = match (min, max) {
+            (_, Some(0)) => String::new(),
+            (0, None) => format!("({} {}*)?", item, more),
+            (0, Some(max)) => format!("({} {}{{0,{}}})?", item, more, max - 1),
+            (min, None) => format!("{} {}{{{},}}", item, more, min - 1),
+            (min, Some(max)) => format!("{} {}{{{},{}}}", item, more, min - 1, max.max(min) - 1),
+        };
+        Ok(Body::Rule(format!("\"[\" space {} \"]\" space", items)))
+    }
+
+    fn reference(&mut self, reference: &str) -> Result<String, String> {
+        let path = reference.strip_prefix("#/").ok_or_else(|| format!("Only local $refs are supported: {}", reference))?;
+        let name = rule_name(&format!("ref-{}", path.rsplit('/').next().unwrap_or(path)));
+        if !self.rules.contains_key(&name) {
+            let target = self.root.pointer(&format!("/{}", path)).ok_or_else(|| format!("Unresolved $ref: {}", reference))?;
+            // Placeholder first, so a recursive schema refers to itself.
+            self.rules.insert(name.clone(), String::new());
+            let rule = self.visit(target, &format!("{}-def", name))?;
+            self.rules.insert(name.clone(), rule);
+        }
+        Ok(name)
+    }
+
+    /// Adds a primitive rule and the primitives it depends on.
+    fn primitive(&mut self, name: &'static str) -> String {
+        if !self.rules.contains_key(name) {
+            let body = PRIMITIVES.iter().find(|(n, _)| *n == name).map_or(ARRAY_RULE, |(_, body)| body);
+            self.rules.insert(name.into(), body.into());
+            let dependencies: &[&'static str] = match name {
+                "string" => &["char"],
+                "value" => &["object", "array", "string", "number", "boolean", "null"],
+                "object" => &["string", "value"],
+                "array" => &["value"],
+                _ => &[],
+            };
+            for dependency in dependencies {
+                self.primitive(dependency);
+            }
+        }
+        name.to_string()
+    }
+}
+
+enum Body {
+    Rule(String),
+    Primitive(&'static str),
+}
+
+fn string(schema: &Map<String, Value>) -> Body {
+    let min = schema.get("minLength").and_then(Value::as_u64);
+    let max = schema.get("maxLength").and_then(Value::as_u64);
+    match (min, max) {
+        (None, None) => Body::Primitive("string"),
+        (min, Some(max)) => Body::Rule(format!(r#""\"" char{{{},{}}} "\"" space"#, min.unwrap_or(0).min(max), max)),
+        (Some(min), None) => Body::Rule(format!(r#""\"" char{{{},}} "\"" space"#, min)),
+    }
+}
+
+fn primitive_name(t: &str) -> &'static str {
+    match t {
+        "integer" => "integer",
+        "number" => "number",
+        "boolean" => "boolean",
+        _ => "null",
+    }
+}
+
+/// The GBNF literal of a JSON value's serialization.
+fn json_literal(value: &Value) -> String {
+    literal(&value.to_string())
+}
+
+fn rule_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule<'a>(grammar: &'a str, name: &str) -> &'a str {
+        let prefix = format!("{} ::= ", name);
+        grammar.lines().find_map(|line| line.strip_prefix(prefix.as_str())).unwrap_or_else(|| panic!("no rule {} in\n{}", name, grammar))
+    }
+
+    #[test]
+    fn required_and_optional_properties() {
+        let grammar = json_schema_to_gbnf(&json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "age": { "type": "integer" } },
+            "required": ["name"]
+        }))
+        .unwrap();
+        assert_eq!(
+            rule(&grammar, "root"),
+            r#""{" space "\"name\"" ":" space string ("," space "\"age\"" ":" space integer)? "}" space"#
+        );
+        assert!(grammar.contains("string ::= "));
+        assert!(grammar.contains("char ::= "));
+    }
+
+    #[test]
+    fn only_optional_properties() {
+        let grammar = json_schema_to_gbnf(&json!({
+            "properties": { "a": { "type": "boolean" }, "b": { "type": "null" } }
+        }))
+        .unwrap();
+        assert_eq!(
+            rule(&grammar, "root"),
+            r#""{" space ("\"a\"" ":" space boolean ("," space "\"b\"" ":" space null)? | "\"b\"" ":" space null)? "}" space"#
+        );
+    }
+
+    #[test]
+    fn properties_follow_schema_key_order() {
+        let schema: Value = serde_json::from_str(
+            r#"{"type": "object", "properties": {"zeta": {"type": "integer"}, "alpha": {"type": "integer"}}, "required": ["zeta", "alpha"]}"#,
+        )
+        .unwrap();
+        let grammar = json_schema_to_gbnf(&schema).unwrap();
+        assert_eq!(
+            rule(&grammar, "root"),
+            r#""{" space "\"zeta\"" ":" space integer "," space "\"alpha\"" ":" space integer "}" space"#
+        );
+    }
+
+    #[test]
+    fn recursive_ref() {
+        let grammar = json_schema_to_gbnf(&json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": { "children": { "type": "array", "items": { "$ref": "#/$defs/node" } } }
+                }
+            },
+            "$ref": "#/$defs/node"
+        }))
+        .unwrap();
+        assert_eq!(rule(&grammar, "root"), "ref-node");
+        assert_eq!(rule(&grammar, "ref-node"), "ref-node-def");
+        assert_eq!(rule(&grammar, "ref-node-def-children-item"), "ref-node");
+        assert_eq!(
+            rule(&grammar, "ref-node-def-children"),
+            r#""[" space (ref-node-def-children-item ("," space ref-node-def-children-item)*)? "]" space"#
+        );
+    }
+
+    #[test]
+    fn unresolved_ref_is_an_error() {
+        assert!(json_schema_to_gbnf(&json!({ "$ref": "#/$defs/missing" })).is_err());
+        assert!(json_schema_to_gbnf(&json!({ "$ref": "https://example.com/schema.json" })).is_err());
+    }
+
+    #[test]
+    fn array_item_counts() {
+        let grammar = json_schema_to_gbnf(&json!({ "type": "array", "items": { "type": "integer" }, "minItems": 1, "maxItems": 3 })).unwrap();
+        assert_eq!(rule(&grammar, "root"), r#""[" space integer ("," space integer){0,2} "]" space"#);
+
+        let grammar = json_schema_to_gbnf(&json!({ "type": "array", "items": { "type": "integer" }, "maxItems": 2 })).unwrap();
+        assert_eq!(rule(&grammar, "root"), r#""[" space (integer ("," space integer){0,1})? "]" space"#);
+
+        let grammar = json_schema_to_gbnf(&json!({ "type": "array", "items": { "type": "integer" }, "minItems": 2 })).unwrap();
+        assert_eq!(rule(&grammar, "root"), r#""[" space integer ("," space integer){1,} "]" space"#);
+    }
+
+    #[test]
+    fn enum_and_const() {
+        let grammar = json_schema_to_gbnf(&json!({ "enum": ["red", 1, null] })).unwrap();
+        assert_eq!(rule(&grammar, "root"), r#"("\"red\"" | "1" | "null") space"#);
+
+        let grammar = json_schema_to_gbnf(&json!({ "const": "x" })).unwrap();
+        assert_eq!(rule(&grammar, "root"), r#""\"x\"" space"#);
+    }
+
+    #[test]
+    fn string_length() {
+        let grammar = json_schema_to_gbnf(&json!({ "type": "string", "minLength": 2, "maxLength": 5 })).unwrap();
+        assert_eq!(rule(&grammar, "root"), r#""\"" char{2,5} "\"" space"#);
+
+        let grammar = json_schema_to_gbnf(&json!({ "type": "string", "minLength": 3 })).unwrap();
+        assert_eq!(rule(&grammar, "root"), r#""\"" char{3,} "\"" space"#);
+    }
+
+    #[test]
+    fn literal_escapes() {
+        assert_eq!(literal("a\"b\\c\n"), r#""a\"b\\c\n""#);
+        assert_eq!(one_of(&["yes".into(), "no".into()]), "root ::= \"yes\" | \"no\"\n");
+    }
+}
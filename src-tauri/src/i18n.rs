@@ -0,0 +1,151 @@
+use crate::state::AppState;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Message catalogs, one JSON resource per language under `locales/`.
+const CATALOG_SOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("hu", include_str!("../locales/hu.json")),
+];
+
+const HU_STOPWORDS: &[&str] = &[
+    "és", "az", "hogy", "nem", "van", "egy", "ez", "mi", "mit", "miért", "hogyan", "meg",
+    "de", "csak", "már", "még", "vagy", "kérlek", "szia", "nekem", "neked", "lesz", "volt",
+];
+const EN_STOPWORDS: &[&str] = &[
+    "the", "and", "is", "are", "what", "how", "why", "you", "to", "of", "in", "it", "this",
+    "that", "can", "do", "does", "please", "hello", "hi", "my", "your", "with", "for", "was",
+];
+
+fn catalogs() -> &'static HashMap<&'static str, HashMap<String, String>> {
+    static CATALOGS: OnceLock<HashMap<&'static str, HashMap<String, String>>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        CATALOG_SOURCES.iter()
+            .map(|(lang, src)| (*lang, serde_json::from_str(src).expect("invalid locale file")))
+            .collect()
+    })
+}
+
+pub fn is_supported(lang: &str) -> bool {
+    CATALOG_SOURCES.iter().any(|(code, _)| *code == lang)
+}
+
+/// Looks up a message, falling back to English and then to the key itself.
+pub fn t(lang: &str, key: &str) -> String {
+    let catalogs = catalogs();
+    catalogs.get(lang)
+        .and_then(|c| c.get(key))
+        .or_else(|| catalogs.get(DEFAULT_LANGUAGE).and_then(|c| c.get(key)))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Like [`t`], replacing each `{}` placeholder with the next argument.
+pub fn tf(lang: &str, key: &str, args: &[&str]) -> String {
+    let mut message = t(lang, key);
+    for arg in args {
+        if let Some(pos) = message.find("{}") {
+            message.replace_range(pos..pos + 2, arg);
+        }
+    }
+    message
+}
+
+/// Guesses the language of a message from diacritics and common stopwords.
+/// Only ő and ű count as Hungarian letters: á, é or ú also appear in French,
+/// Spanish or Portuguese text and in names. Returns `None` when there is not
+/// enough signal (e.g. "ok" or a code snippet).
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let lower = text.to_lowercase();
+    let mut hu = lower.chars().filter(|c| "őű".contains(*c)).count() as u32;
+    let mut en = 0u32;
+
+    for word in lower.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        if HU_STOPWORDS.contains(&word) { hu += 2; }
+        if EN_STOPWORDS.contains(&word) { en += 2; }
+    }
+
+    if hu == 0 && en == 0 {
+        None
+    } else if hu > en {
+        Some("hu")
+    } else {
+        Some("en")
+    }
+}
+
+/// The language backend messages should use: the configured one, or the
+/// language of the active chat when set to "auto". Does not lock `chats`, so
+/// it is safe to call while holding it.
+pub fn ui_language(state: &AppState) -> String {
+    let configured = state.settings.lock().unwrap().language.clone();
+    if is_supported(&configured) {
+        configured
+    } else {
+        state.active_chat_language.lock().unwrap().clone()
+    }
+}
+
+/// The language Mia should answer this message in. With "auto" it is detected
+/// from the message, falling back to the language last detected in the chat.
+/// The detection is remembered on that chat only; without a `chat_id` nothing
+/// is remembered. Must not be called while holding `chats`.
+pub fn reply_language(state: &AppState, chat_id: Option<&str>, message: &str) -> String {
+    let configured = state.settings.lock().unwrap().language.clone();
+    if is_supported(&configured) {
+        return configured;
+    }
+    let detected = detect_language(message).map(str::to_string);
+    let Some(chat_id) = chat_id else {
+        return detected.unwrap_or_else(|| ui_language(state));
+    };
+    let lang = {
+        let mut chats = state.chats.lock().unwrap();
+        match chats.get_mut(chat_id) {
+            Some(chat) => {
+                if detected.is_some() {
+                    chat.language.clone_from(&detected);
+                }
+                chat.language.clone()
+            }
+            None => detected,
+        }
+    }
+    .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    if *state.active_chat_id.lock().unwrap() == chat_id {
+        state.active_chat_language.lock().unwrap().clone_from(&lang);
+    }
+    lang
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_language_from_stopwords_and_letters() {
+        assert_eq!(detect_language("Szia, mit csinálsz ma?"), Some("hu"));
+        assert_eq!(detect_language("Hello, what is the weather like?"), Some("en"));
+        assert_eq!(detect_language("Erdős Pál"), Some("hu"));
+    }
+
+    #[test]
+    fn no_signal_without_stopwords() {
+        assert_eq!(detect_language("ok"), None);
+        assert_eq!(detect_language("let x = vec![1, 2];"), None);
+        // á and é alone are not Hungarian.
+        assert_eq!(detect_language("José café"), None);
+    }
+
+    #[test]
+    fn catalogs_translate_with_fallback() {
+        assert_eq!(tf("en", "download_invalid_path", &["a/../b"]), "Invalid repository path: a/../b");
+        assert_eq!(t("xx", "download_cancelled"), t(DEFAULT_LANGUAGE, "download_cancelled"));
+        let en = &catalogs()["en"];
+        let hu = &catalogs()["hu"];
+        let missing: Vec<_> = en.keys().filter(|key| !hu.contains_key(*key)).collect();
+        assert!(missing.is_empty(), "missing Hungarian strings: {:?}", missing);
+    }
+}
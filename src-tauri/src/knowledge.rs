@@ -0,0 +1,373 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::attachments::import_file;
+use crate::i18n;
+use crate::state::{get_now, AppState};
+
+/// Upper bound on indexed files, so pointing Mia at a home directory stays usable.
+const MAX_FILES: usize = 5000;
+/// Changes are re-indexed once the directory has been quiet this long.
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+/// Images would be stored for vision models, but add nothing to text search.
+const SKIPPED_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IndexedFile {
+    #[serde(rename = "attachmentId")]
+    pub attachment_id: String,
+    pub modified: u64,
+    pub size: u64,
+}
+
+/// A directory indexed as a set of attachments, keyed by path relative to `root`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KnowledgeBase {
+    pub id: String,
+    pub name: String,
+    pub root: String,
+    pub files: HashMap<String, IndexedFile>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: u64,
+}
+
+/// What the UI sees of a knowledge base; the file map stays in the backend.
+#[derive(Serialize, Clone)]
+pub struct KnowledgeBaseInfo {
+    pub id: String,
+    pub name: String,
+    pub root: String,
+    #[serde(rename = "fileCount")]
+    pub file_count: usize,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: u64,
+}
+
+impl KnowledgeBase {
+    pub fn new(name: String, root: String) -> Self {
+        Self { id: Uuid::new_v4().to_string(), name, root, files: HashMap::new(), updated_at: 0 }
+    }
+
+    pub fn info(&self) -> KnowledgeBaseInfo {
+        KnowledgeBaseInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            root: self.root.clone(),
+            file_count: self.files.len(),
+            updated_at: self.updated_at,
+        }
+    }
+
+    pub fn attachment_ids(&self) -> impl Iterator<Item = &String> {
+        self.files.values().map(|f| &f.attachment_id)
+    }
+}
+
+#[derive(Default)]
+pub struct KnowledgeStore {
+    bases: HashMap<String, KnowledgeBase>,
+    path: Option<PathBuf>,
+    /// Bases being indexed right now; `true` when changes arrived meanwhile
+    /// and another pass is due.
+    indexing: HashMap<String, bool>,
+}
+
+impl KnowledgeStore {
+    pub fn load(path: PathBuf) -> Self {
+        let bases = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { bases, path: Some(path), indexing: HashMap::new() }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&KnowledgeBase> {
+        self.bases.get(id)
+    }
+
+    pub fn list(&self) -> Vec<KnowledgeBaseInfo> {
+        let mut list: Vec<KnowledgeBaseInfo> = self.bases.values().map(KnowledgeBase::info).collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.bases.keys().cloned().collect()
+    }
+
+    pub fn insert(&mut self, base: KnowledgeBase) -> Result<(), String> {
+        self.bases.insert(base.id.clone(), base);
+        self.save()
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<Option<KnowledgeBase>, String> {
+        let removed = self.bases.remove(id);
+        self.save()?;
+        Ok(removed)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&self.bases).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct IndexProgress {
+    #[serde(rename = "knowledgeBaseId")]
+    knowledge_base_id: String,
+    done: usize,
+    total: usize,
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn is_skipped_extension(path: &Path) -> bool {
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    SKIPPED_EXTENSIONS.contains(&extension.as_str())
+}
+
+/// Files under `root` that are not excluded by `.gitignore`, `.ignore` or
+/// hidden-file rules, relative path first.
+fn walk(root: &Path) -> Vec<(String, PathBuf)> {
+    ignore::WalkBuilder::new(root)
+        .hidden(true)
+        .git_ignore(true)
+        .require_git(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter(|entry| !is_skipped_extension(entry.path()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?.to_string_lossy().replace('\\', "/");
+            Some((relative, entry.into_path()))
+        })
+        .take(MAX_FILES)
+        .collect()
+}
+
+/// Whether a change to `path` can be left alone because [`walk`] would not
+/// index it: hidden, matched by a `.gitignore` / `.ignore` between `root` and
+/// the file, or an image. Edits to the ignore files themselves count.
+fn is_ignored(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else { return true };
+    let name = relative.file_name().and_then(|s| s.to_str()).unwrap_or("");
+    if name == ".gitignore" || name == ".ignore" {
+        return false;
+    }
+    let hidden = relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+    if hidden || is_skipped_extension(path) {
+        return true;
+    }
+
+    // The deepest directory with a matching rule decides, as in `walk`.
+    let is_dir = path.is_dir();
+    let mut dir = path.parent();
+    while let Some(current) = dir.filter(|d| d.starts_with(root)) {
+        for file in [".ignore", ".gitignore"] {
+            let mut builder = ignore::gitignore::GitignoreBuilder::new(current);
+            if current.join(file).is_file() && builder.add(current.join(file)).is_none() {
+                if let Ok(rules) = builder.build() {
+                    match rules.matched_path_or_any_parents(path, is_dir) {
+                        ignore::Match::Ignore(_) => return true,
+                        ignore::Match::Whitelist(_) => return false,
+                        ignore::Match::None => {}
+                    }
+                }
+            }
+        }
+        dir = current.parent();
+    }
+    false
+}
+
+/// True when no chat message and no other knowledge base uses the attachment.
+pub fn is_orphaned(state: &AppState, attachment_id: &str, base_id: &str) -> bool {
+    let in_chats = state.chats.lock().unwrap().values()
+        .flat_map(|chat| chat.messages.iter())
+        .any(|m| m.attachments.iter().flatten().any(|id| id == attachment_id));
+    let in_bases = state.knowledge.lock().unwrap().bases.values()
+        .filter(|b| b.id != base_id)
+        .any(|b| b.attachment_ids().any(|id| id == attachment_id));
+    !in_chats && !in_bases
+}
+
+/// Removes a base's entry from `KnowledgeStore::indexing` when indexing
+/// ends, also by error or panic.
+struct IndexingGuard<'a> {
+    state: &'a AppState,
+    base_id: &'a str,
+}
+
+impl Drop for IndexingGuard<'_> {
+    fn drop(&mut self) {
+        self.state.knowledge.lock().unwrap().indexing.remove(self.base_id);
+    }
+}
+
+/// Brings a knowledge base in line with its directory: new and changed files
+/// are extracted, unchanged ones kept, deleted ones dropped. Only one pass
+/// runs per base; a call during it fails with `knowledge_busy` and makes the
+/// running pass go once more, so no change is missed.
+pub fn index(handle: &AppHandle, base_id: &str) -> Result<KnowledgeBaseInfo, String> {
+    let state = handle.state::<AppState>();
+    let lang = i18n::ui_language(&state);
+    {
+        let mut store = state.knowledge.lock().unwrap();
+        if store.get(base_id).is_none() {
+            return Err(i18n::t(&lang, "knowledge_not_found"));
+        }
+        if let Some(again) = store.indexing.get_mut(base_id) {
+            *again = true;
+            return Err(i18n::t(&lang, "knowledge_busy"));
+        }
+        store.indexing.insert(base_id.to_string(), false);
+    }
+    let _guard = IndexingGuard { state: &state, base_id };
+
+    loop {
+        let info = index_once(handle, &state, base_id, &lang)?;
+        let mut store = state.knowledge.lock().unwrap();
+        if !store.indexing.get_mut(base_id).is_some_and(std::mem::take) {
+            return Ok(info);
+        }
+    }
+}
+
+fn index_once(handle: &AppHandle, state: &AppState, base_id: &str, lang: &str) -> Result<KnowledgeBaseInfo, String> {
+    let mut base = state.knowledge.lock().unwrap().get(base_id).cloned().ok_or_else(|| i18n::t(lang, "knowledge_not_found"))?;
+    let root = PathBuf::from(&base.root);
+
+    let files = walk(&root);
+    let total = files.len();
+    let mut indexed: HashMap<String, IndexedFile> = HashMap::new();
+    for (i, (relative, path)) in files.iter().enumerate() {
+        if i % 25 == 0 {
+            let _ = handle.emit("mia-knowledge-progress", IndexProgress { knowledge_base_id: base.id.clone(), done: i, total });
+        }
+        let Ok(metadata) = fs::metadata(path) else { continue };
+        let (modified, size) = (modified_secs(&metadata), metadata.len());
+
+        let unchanged = base.files.get(relative)
+            .filter(|f| f.modified == modified && f.size == size)
+            .filter(|f| state.attachments.lock().unwrap().get(&f.attachment_id).is_some());
+        if let Some(file) = unchanged {
+            indexed.insert(relative.clone(), file.clone());
+            continue;
+        }
+        // Files no extractor handles (binaries, archives, images) are skipped.
+        if let Ok(attachment) = import_file(state, path, relative.clone(), i18n::DEFAULT_LANGUAGE) {
+            indexed.insert(relative.clone(), IndexedFile { attachment_id: attachment.id, modified, size });
+        }
+    }
+
+    let kept: HashSet<&String> = indexed.values().map(|f| &f.attachment_id).collect();
+    let dropped: Vec<String> = base.attachment_ids().filter(|id| !kept.contains(id)).cloned().collect();
+    let known: HashSet<&String> = base.attachment_ids().collect();
+    let added: Vec<String> = indexed.values().map(|f| &f.attachment_id).filter(|id| !known.contains(id)).cloned().collect();
+    base.files = indexed;
+    base.updated_at = get_now();
+    let info = base.info();
+    let stored = {
+        let mut store = state.knowledge.lock().unwrap();
+        // Deleted while indexing: do not bring it back.
+        if store.get(base_id).is_none() {
+            Err(i18n::t(lang, "knowledge_not_found"))
+        } else {
+            store.insert(base)
+        }
+    };
+    if let Err(e) = stored {
+        // Nothing refers to the files imported in this pass.
+        for id in added {
+            if is_orphaned(state, &id, base_id) {
+                let _ = state.attachments.lock().unwrap().remove(&id);
+            }
+        }
+        return Err(e);
+    }
+
+    for id in dropped {
+        if is_orphaned(state, &id, base_id) {
+            let _ = state.attachments.lock().unwrap().remove(&id);
+        }
+    }
+
+    let _ = handle.emit("mia-knowledge-progress", IndexProgress { knowledge_base_id: info.id.clone(), done: total, total });
+    println!(">>> Tudásbázis indexelve: {} ({} fájl)", info.name, info.file_count);
+    Ok(info)
+}
+
+/// Re-indexes the knowledge base whenever files under its root change.
+pub fn watch(handle: &AppHandle, base_id: &str) -> Result<(), String> {
+    let state = handle.state::<AppState>();
+    let root = state.knowledge.lock().unwrap().get(base_id).map(|b| PathBuf::from(&b.root))
+        .ok_or_else(|| i18n::t(&i18n::ui_language(&state), "knowledge_not_found"))?;
+
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    watcher.watch(&root, RecursiveMode::Recursive).map_err(|e| e.to_string())?;
+
+    let handle = handle.clone();
+    let id = base_id.to_string();
+    thread::spawn(move || {
+        let is_relevant = |event: notify::Result<notify::Event>| {
+            event.is_ok_and(|e| !e.kind.is_access() && e.paths.iter().any(|p| !is_ignored(&root, p)))
+        };
+        // Ends when the watcher (and with it the sender) is dropped.
+        while let Ok(event) = rx.recv() {
+            let relevant = is_relevant(event);
+            // Wait for the burst of events (a git checkout, a build) to settle.
+            let mut more = false;
+            loop {
+                match rx.recv_timeout(WATCH_DEBOUNCE) {
+                    Ok(event) => more |= is_relevant(event),
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if relevant || more {
+                if let Ok(info) = index(&handle, &id) {
+                    let _ = handle.emit("mia-knowledge-updated", info);
+                }
+            }
+        }
+    });
+
+    state.knowledge_watchers.lock().unwrap().insert(base_id.to_string(), watcher);
+    Ok(())
+}
+
+pub fn unwatch(state: &AppState, base_id: &str) {
+    state.knowledge_watchers.lock().unwrap().remove(base_id);
+}
+
+/// Startup: catch up with changes made while the app was closed, then watch.
+pub fn watch_all(handle: &AppHandle) {
+    let ids = handle.state::<AppState>().knowledge.lock().unwrap().ids();
+    let handle = handle.clone();
+    thread::spawn(move || {
+        for id in ids {
+            if let Err(e) = index(&handle, &id).and_then(|_| watch(&handle, &id)) {
+                println!(">>> Tudásbázis figyelése sikertelen ({}): {}", id, e);
+            }
+        }
+    });
+}
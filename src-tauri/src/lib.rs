@@ -1,14 +1,32 @@
+mod attachments;
+mod auto_mode;
 mod commands;
+mod downloads;
+mod extract;
+mod gguf;
+mod grammar;
+mod i18n;
+mod knowledge;
+mod model_manager;
+mod personas;
+mod rag;
 mod state;
+mod web_cache;
 
-use crate::state::{AppSettings, AppState, ChatMessage};
+use crate::state::{AppSettings, AppState, Chat, ChatMessage};
+use crate::attachments::AttachmentStore;
+use crate::extract::ExtractorRegistry;
+use crate::knowledge::KnowledgeStore;
+use crate::model_manager::ModelLoader;
+use crate::web_cache::WebCache;
 use llama_cpp_2::llama_backend::LlamaBackend;
 use std::fs;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use sysinfo::{ProcessesToUpdate, System};
-use tauri::{Emitter, LogicalPosition, Manager, WindowEvent};
+use tauri::{LogicalPosition, Manager, WindowEvent};
+use tauri_plugin_global_shortcut::ShortcutState;
 use std::collections::HashMap;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -20,44 +38,93 @@ pub fn run() {
     sys.refresh_all();
     let system_info = Arc::new(Mutex::new(sys));
 
-    let backend = match LlamaBackend::init() {
-        Ok(b) => b,
+    // Without a backend the app still starts: `get_system_stats` reports the
+    // failure (backend "none" with the error), the floating icon shows it, and
+    // loading a model fails with `backend_unavailable`.
+    let (backend, backend_error) = match LlamaBackend::init() {
+        Ok(b) => (Some(Arc::new(b)), None),
         Err(e) => {
-            eprintln!("Hiba: Nem sikerült a Vulkan inicializálása: {:?}", e);
-            return;
+            eprintln!("Hiba: Nem sikerült a llama.cpp backend inicializálása: {:?}", e);
+            (None, Some(e.to_string()))
         }
     };
 
     tauri::Builder::default()
-        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        commands::clipboard::open_with_clipboard(app);
+                    }
+                })
+                .build(),
+        )
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(AppState { 
             games_list: shared_games,
             sys: system_info,
             mia_brain: Arc::new(Mutex::new(None)),
-            backend: Arc::new(backend),
+            model_loader: Mutex::new(ModelLoader::default()),
+            backend,
+            backend_error,
             chats: Mutex::new(HashMap::new()),
             active_chat_id: Mutex::new(String::new()),
-            current_mode: Mutex::new(crate::state::MiaMode::Auto)
+            settings: Mutex::new(AppSettings::default()),
+            web_cache: Mutex::new(WebCache::default()),
+            personas: Mutex::new(personas::default_personas()),
+            active_chat_language: Mutex::new(i18n::DEFAULT_LANGUAGE.into()),
+            attachments: Mutex::new(AttachmentStore::default()),
+            extractors: ExtractorRegistry::default(),
+            knowledge: Mutex::new(KnowledgeStore::default()),
+            knowledge_watchers: Mutex::new(HashMap::new()),
+            downloads: Mutex::new(HashMap::new()),
         })
         .invoke_handler(tauri::generate_handler![
             commands::chat::ask_mia,
             commands::chat::load_mia,
             commands::chat::unload_mia,
+            commands::chat::set_chat_model,
             commands::chat::create_new_chat,
             commands::chat::get_all_chats,
             commands::chat::switch_chat,
             commands::chat::get_chat_history,
             commands::chat::delete_chat,
+            commands::chat::generate_chat_title,
+            commands::structured::generate_structured,
             commands::window::toggle_main_window, 
             commands::window::hide_main_window, 
             commands::window::maximize_main_window,
             commands::system::get_system_stats,
+            commands::system::get_model_info,
+            commands::system::get_available_models,
+            commands::models::download_model,
+            commands::models::cancel_download,
             commands::settings::save_settings,
             commands::settings::get_settings,
+            commands::settings::translate,
             commands::chat::set_mia_mode,
-            commands::chat::upload_file
+            commands::attachments::upload_file,
+            commands::attachments::get_attachments,
+            commands::attachments::get_attachment_page,
+            commands::attachments::delete_attachment,
+            commands::documents::summarize_document,
+            commands::documents::ask_document,
+            commands::knowledge::ingest_directory,
+            commands::knowledge::get_knowledge_bases,
+            commands::knowledge::reindex_knowledge_base,
+            commands::knowledge::delete_knowledge_base,
+            commands::knowledge::attach_knowledge_base,
+            commands::knowledge::detach_knowledge_base,
+            commands::clipboard::read_clipboard,
+            commands::clipboard::attach_clipboard,
+            commands::search::clear_web_cache,
+            commands::search::get_web_cache_info,
+            commands::personas::get_personas,
+            commands::personas::save_persona,
+            commands::personas::delete_persona,
+            commands::personas::reset_persona
         ])
         .on_window_event(|window, event| {
             if window.label() == "main" {
@@ -65,27 +132,15 @@ pub fn run() {
                     WindowEvent::CloseRequested { api, .. } => {
                         api.prevent_close(); 
                         let _ = window.hide();
-                        
-                        let state = window.state::<AppState>();
-                        let mut brain = state.mia_brain.lock().unwrap();
-                        
-                        if brain.is_some() {
-                            let old_brain = brain.take();
-                            std::mem::drop(old_brain); 
-                            let _ = window.emit("mia-loading-status", false);
-                            println!(">>> X gomb elkapva: Mia agya kényszerítve törölve, VRAM felszabadítva.");
-                        }
+                        model_manager::on_window_hidden(window.app_handle());
 
                         if let Some(floater) = window.get_webview_window("floater") {
                             let _ = floater.show();
                         }
                     },
                     WindowEvent::Destroyed => {
-                        let state = window.state::<AppState>();
-                        let mut brain = state.mia_brain.lock().unwrap();
-                        if brain.is_some() {
-                            let _ = brain.take();
-                        }
+                        model_manager::release(window.app_handle(), "exit");
+                        let _ = window.app_handle().state::<AppState>().web_cache.lock().unwrap().flush();
                     },
                     _ => {}
                 }
@@ -99,30 +154,73 @@ pub fn run() {
                 
                 if chats_path.exists() {
                     if let Ok(content) = fs::read_to_string(&chats_path) {
-                        if let Ok(loaded_chats) = serde_json::from_str::<HashMap<String, Vec<ChatMessage>>>(&content) {
+                        // Older files stored only the message list per chat
+                        let parsed = serde_json::from_str::<HashMap<String, Chat>>(&content).ok().or_else(|| {
+                            serde_json::from_str::<HashMap<String, Vec<ChatMessage>>>(&content).ok().map(|legacy| {
+                                legacy.into_iter()
+                                    .map(|(id, messages)| (id, Chat { messages, ..Chat::default() }))
+                                    .collect()
+                            })
+                        });
+                        if let Some(loaded_chats) = parsed {
                             let state = handle.state::<AppState>();
                             
                             let mut chats = state.chats.lock().unwrap();
                             *chats = loaded_chats;
 
-                            if let Some(first_id) = chats.keys().next() {
+                            if let Some((first_id, first)) = chats.iter().next() {
                                 let mut active_id = state.active_chat_id.lock().unwrap();
                                 *active_id = first_id.clone();
+                                if let Some(lang) = &first.language {
+                                    state.active_chat_language.lock().unwrap().clone_from(lang);
+                                }
                             }
                             println!(">>> Mia emlékei betöltve ({} beszélgetés)", chats.len());
                         }
                     }
                 }
+
+                let cache = WebCache::load(app_data_dir.join("web_cache.json"));
+                println!(">>> Webes gyorsítótár betöltve ({} bejegyzés)", cache.len());
+                *handle.state::<AppState>().web_cache.lock().unwrap() = cache;
+
+                let loaded_personas = personas::load_personas(&app_data_dir.join("personas.json"));
+                println!(">>> Personák betöltve ({} db)", loaded_personas.len());
+                *handle.state::<AppState>().personas.lock().unwrap() = loaded_personas;
+
+                *handle.state::<AppState>().attachments.lock().unwrap() = AttachmentStore::load(app_data_dir.join("attachments"));
+                *handle.state::<AppState>().knowledge.lock().unwrap() = KnowledgeStore::load(app_data_dir.join("knowledge_bases.json"));
             }
 
             let config_dir = handle.path().app_config_dir().unwrap();
             let file_path = config_dir.join("settings.json");
             if let Ok(content) = fs::read_to_string(&file_path) {
-                if let Ok(loaded) = serde_json::from_str::<AppSettings>(&content) {
-                    handle.state::<AppState>().games_list.lock().unwrap().clone_from(&loaded.games);
+                if let Ok(mut loaded) = serde_json::from_str::<AppSettings>(&content) {
+                    let state = handle.state::<AppState>();
+                    let mut all_personas = state.personas.lock().unwrap();
+                    if personas::migrate_legacy_keywords(&mut all_personas, &mut loaded.auto_mode_rules) {
+                        // Rewrite both files so the lists are moved exactly once.
+                        if let Ok(app_data_dir) = handle.path().app_data_dir() {
+                            let _ = personas::save_personas(&app_data_dir.join("personas.json"), &all_personas);
+                        }
+                        if let Ok(json) = serde_json::to_string_pretty(&loaded) {
+                            let _ = fs::write(&file_path, json);
+                        }
+                    }
+                    drop(all_personas);
+                    state.games_list.lock().unwrap().clone_from(&loaded.games);
+                    *state.settings.lock().unwrap() = loaded;
                 }
             }
 
+            let shortcut = handle.state::<AppState>().settings.lock().unwrap().clipboard_shortcut.clone();
+            let lang = i18n::ui_language(&handle.state::<AppState>());
+            if let Err(e) = commands::clipboard::register_clipboard_shortcut(&handle, &lang, "", &shortcut) {
+                println!(">>> {}", e);
+            }
+            knowledge::watch_all(&handle);
+            model_manager::watch_release(&handle);
+
             if let Some(floater) = app.get_webview_window("floater") {
                 if let Ok(Some(monitor)) = floater.current_monitor() {
                     let size = monitor.size();
@@ -135,6 +233,7 @@ pub fn run() {
 
             thread::spawn(move || {
                 let mut watcher_sys = System::new_all();
+                let mut was_running = false;
                 loop {
                     watcher_sys.refresh_processes(ProcessesToUpdate::All, true);
                     let games = games_for_watcher.lock().unwrap();
@@ -144,6 +243,11 @@ pub fn run() {
                     });
                     drop(games); 
 
+                    if is_running && !was_running {
+                        model_manager::on_game_started(&handle);
+                    }
+                    was_running = is_running;
+
                     if let Some(floater) = handle.get_webview_window("floater") {
                         let main_v = handle.get_webview_window("main")
                             .map(|m| m.is_visible().unwrap_or(false)).unwrap_or(false);
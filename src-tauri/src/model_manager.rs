@@ -0,0 +1,759 @@
+use llama_cpp_2::llama_backend::LlamaBackend;
+use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::LlamaModel;
+use llama_cpp_2::mtmd::{MtmdContext, MtmdContextParams};
+use llama_cpp_2::LlamaBackendDeviceType;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::{c_void, CString};
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+use std::sync::{MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+use crate::gguf;
+use crate::i18n;
+use crate::state::{AppState, DraftModel, MiaModel};
+
+pub const MODELS_DIR: &str = "models";
+/// Used by chats and personas that do not pick a model.
+pub const DEFAULT_MODEL: &str = "mia-brain-q4.gguf";
+/// Optional vision projector of the default model; other models look for
+/// `<name>-mmproj.gguf`. Without one image attachments are rejected.
+const DEFAULT_MMPROJ: &str = "mia-brain-mmproj.gguf";
+
+const RELEASE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How often, and at most how long, a load waits for the requests pinning the
+/// active model before swapping it out anyway.
+const PIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_PIN_WAIT: Duration = Duration::from_secs(60);
+/// VRAM left free for llama.cpp's compute buffers and other applications.
+const VRAM_HEADROOM_MB: u64 = 512;
+/// Largest context the chat commands create; sizes the KV cache estimate.
+const KV_CACHE_CONTEXT: u64 = 4096;
+
+/// When the loaded model is dropped to give VRAM back.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum ReleasePolicy {
+    /// As soon as the chat window is hidden or a watched game starts.
+    Immediate,
+    /// After `release_idle_minutes` without use while the window is hidden,
+    /// or as soon as a watched game starts.
+    #[default]
+    AfterIdle,
+    /// Only when a watched game starts or free memory drops below `release_min_free_mb`.
+    OnPressure,
+    /// Never; reopening is instant at the cost of the memory.
+    KeepResident,
+}
+
+type LoadResult = Option<Result<(), String>>;
+
+/// Shared state of the model lifecycle: the load in progress, if any, when a
+/// model was last needed, and the models kept in memory besides the active
+/// one in `AppState::mia_brain`.
+pub struct ModelLoader {
+    /// The model being loaded and the channel its result arrives on.
+    pending: Option<(String, watch::Receiver<LoadResult>)>,
+    last_used: Instant,
+    /// Copies of the active model's `runs_on` and `info`, readable while it generates.
+    active: Option<ActiveBackend>,
+    info: Option<ModelInfo>,
+    layouts: HashMap<String, Layout>,
+    /// Swapped-out models, most recently used first; see `residentModels`.
+    resident: Vec<MiaModel>,
+    /// Requests inside `lock_model`, per model name.
+    pins: HashMap<String, usize>,
+}
+
+impl Default for ModelLoader {
+    fn default() -> Self {
+        Self {
+            pending: None,
+            last_used: Instant::now(),
+            active: None,
+            info: None,
+            layouts: HashMap::new(),
+            resident: Vec::new(),
+            pins: HashMap::new(),
+        }
+    }
+}
+
+pub fn model_path(name: &str) -> PathBuf {
+    Path::new(MODELS_DIR).join(name)
+}
+
+fn mmproj_path(name: &str) -> PathBuf {
+    if name == DEFAULT_MODEL {
+        return model_path(DEFAULT_MMPROJ);
+    }
+    model_path(&format!("{}-mmproj.gguf", name.trim_end_matches(".gguf")))
+}
+
+/// GGUF files in `MODELS_DIR` that chats and personas can pick, without the
+/// vision projectors.
+pub fn available_models() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(MODELS_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".gguf") && !name.contains("mmproj"))
+        .collect();
+    names.sort();
+    names
+}
+
+/// What `get_model_info` reports about the loaded model, collected from its
+/// metadata when it is loaded.
+#[derive(Serialize, Clone)]
+pub struct ModelInfo {
+    pub file: String,
+    pub architecture: Option<String>,
+    #[serde(rename = "parameterCount")]
+    pub parameter_count: u64,
+    /// llama.cpp file type name, e.g. "Q4_K_M".
+    pub quantization: Option<String>,
+    #[serde(rename = "contextLength")]
+    pub context_length: u32,
+    #[serde(rename = "vocabSize")]
+    pub vocab_size: i32,
+    #[serde(rename = "hasChatTemplate")]
+    pub has_chat_template: bool,
+    #[serde(rename = "hasVision")]
+    pub has_vision: bool,
+    #[serde(rename = "loadSeconds")]
+    pub load_seconds: f64,
+    #[serde(rename = "gpuLayers")]
+    pub gpu_layers: u32,
+    /// Draft model used for speculative decoding.
+    #[serde(rename = "draftModel")]
+    pub draft_model: Option<String>,
+}
+
+/// Name of a `general.file_type` value (llama.cpp's `llama_ftype`).
+fn quantization_name(file_type: u32) -> Option<&'static str> {
+    Some(match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        19 => "IQ2_XXS",
+        20 => "IQ2_XS",
+        21 => "Q2_K_S",
+        22 => "IQ3_XS",
+        23 => "IQ3_XXS",
+        24 => "IQ1_S",
+        25 => "IQ4_NL",
+        26 => "IQ3_S",
+        27 => "IQ3_M",
+        28 => "IQ2_S",
+        29 => "IQ2_M",
+        30 => "IQ4_XS",
+        31 => "IQ1_M",
+        32 => "BF16",
+        36 => "TQ1_0",
+        37 => "TQ2_0",
+        _ => return None,
+    })
+}
+
+fn model_info(path: &Path, model: &LlamaModel, has_vision: bool, load_seconds: f64, gpu_layers: u32) -> ModelInfo {
+    let quantization = model
+        .meta_val_str("general.file_type")
+        .ok()
+        .and_then(|file_type| file_type.parse().ok())
+        .map(|file_type| quantization_name(file_type).map_or_else(|| format!("type {}", file_type), str::to_string));
+    ModelInfo {
+        file: path.display().to_string(),
+        architecture: model.meta_val_str("general.architecture").ok(),
+        parameter_count: model.n_params(),
+        quantization,
+        context_length: model.n_ctx_train(),
+        vocab_size: model.n_vocab(),
+        has_chat_template: model.chat_template(None).is_ok(),
+        has_vision,
+        load_seconds,
+        gpu_layers,
+        draft_model: None,
+    }
+}
+
+/// Metadata of the loaded model; `None` while nothing is loaded.
+pub fn loaded_model_info(state: &AppState) -> Option<ModelInfo> {
+    let info = state.model_loader.lock().unwrap().info.clone();
+    // A locked brain is busy generating, so it is loaded.
+    let loaded = state.mia_brain.try_lock().map_or(true, |brain| brain.is_some());
+    info.filter(|_| loaded)
+}
+
+/// How much VRAM one offloaded layer takes, estimated from the GGUF header.
+#[derive(Clone, Copy)]
+struct Layout {
+    /// Repeating blocks plus the output layer, which llama.cpp offloads last.
+    layers: u32,
+    layer_bytes: u64,
+}
+
+fn read_layout(path: &Path) -> Result<Layout, String> {
+    let metadata = gguf::Metadata::read(path)?;
+    let size = fs::metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?.len();
+    let blocks = metadata.arch_uint("block_count").ok_or_else(|| format!("{}: no block_count in GGUF metadata", path.display()))?;
+    let layers = blocks + 1;
+
+    // f16 K and V for every position of the largest context; grouped-query
+    // attention shrinks them by head_count / head_count_kv.
+    let embedding = metadata.arch_uint("embedding_length").unwrap_or(0);
+    let heads = metadata.arch_uint("attention.head_count").unwrap_or(1).max(1);
+    let kv_heads = metadata.arch_uint("attention.head_count_kv").unwrap_or(heads);
+    let kv_bytes = 2 * 2 * KV_CACHE_CONTEXT * embedding * kv_heads / heads;
+
+    Ok(Layout { layers: layers as u32, layer_bytes: size / layers + kv_bytes })
+}
+
+/// The layout of a model, read once per model.
+fn layout(state: &AppState, name: &str) -> Result<Layout, String> {
+    if let Some(layout) = state.model_loader.lock().unwrap().layouts.get(name) {
+        return Ok(*layout);
+    }
+    let layout = read_layout(&model_path(name))?;
+    state.model_loader.lock().unwrap().layouts.insert(name.to_string(), layout);
+    Ok(layout)
+}
+
+/// Drops the cached layout of a model whose file was replaced.
+pub fn forget_layout(state: &AppState, name: &str) {
+    state.model_loader.lock().unwrap().layouts.remove(name);
+}
+
+/// Layers to offload: the `gpuLayers` setting, also when no GPU is detected,
+/// or in auto mode as many as fit in the free VRAM of the smallest GPU after
+/// the headroom. Under `OnPressure` the headroom also covers
+/// `releaseMinFreeMb`; otherwise the model's own layers would push free VRAM
+/// under the threshold and get it released.
+fn planned_gpu_layers(state: &AppState, name: &str) -> Result<u32, String> {
+    let headroom = {
+        let settings = state.settings.lock().unwrap();
+        if let Some(layers) = settings.gpu_layers {
+            return Ok(layers);
+        }
+        match settings.release_policy {
+            ReleasePolicy::OnPressure => settings.release_min_free_mb + VRAM_HEADROOM_MB,
+            _ => VRAM_HEADROOM_MB,
+        }
+    };
+    let Some(free_mb) = free_vram_mb() else { return Ok(0) };
+    let layout = layout(state, name)?;
+    let budget = free_mb.saturating_sub(headroom) * 1024 * 1024;
+    Ok((budget / layout.layer_bytes.max(1)).min(layout.layers as u64) as u32)
+}
+
+/// Layers to retry with after loading with `gpu_layers` failed: about
+/// `VRAM_HEADROOM_MB` worth of layers fewer, so the retry stays close to the
+/// largest count that fits. Halves when the layout is unknown.
+fn fewer_gpu_layers(state: &AppState, name: &str, gpu_layers: u32) -> u32 {
+    match layout(state, name) {
+        Ok(layout) => {
+            let step = (VRAM_HEADROOM_MB * 1024 * 1024).div_ceil(layout.layer_bytes.max(1)).max(1);
+            gpu_layers.min(layout.layers).saturating_sub(step as u32)
+        }
+        Err(_) => gpu_layers / 2,
+    }
+}
+
+/// What inference runs on, as reported by `get_system_stats`.
+#[derive(Serialize, Clone)]
+pub struct ActiveBackend {
+    /// "gpu", "cpu", or "none" when llama.cpp could not be initialized.
+    pub kind: &'static str,
+    /// GPU description as reported by llama.cpp.
+    pub device: Option<String>,
+    #[serde(rename = "gpuLayers")]
+    pub gpu_layers: u32,
+    pub threads: i32,
+    /// Why inference is unavailable, when `kind` is "none".
+    pub error: Option<String>,
+}
+
+fn gpu_device() -> Option<String> {
+    llama_cpp_2::list_llama_ggml_backend_devices()
+        .into_iter()
+        .find(|device| device.device_type == LlamaBackendDeviceType::Gpu)
+        .map(|device| device.description)
+}
+
+/// The `cpuThreads` setting, or one less than the physical cores: hyper-threads
+/// slow generation down and the UI keeps a core.
+fn inference_threads(state: &AppState) -> i32 {
+    let configured = state.settings.lock().unwrap().cpu_threads;
+    if configured > 0 {
+        return configured as i32;
+    }
+    let logical = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let physical = System::physical_core_count().unwrap_or(logical / 2);
+    physical.saturating_sub(1).max(1) as i32
+}
+
+/// The backend of the active model, or what loading the default model would use right now.
+pub fn active_backend(state: &AppState) -> ActiveBackend {
+    let active = state.model_loader.lock().unwrap().active.clone();
+    // A locked brain is busy generating, so it is loaded.
+    let loaded = state.mia_brain.try_lock().map_or(true, |brain| brain.is_some());
+    if let Some(active) = active.filter(|_| loaded) {
+        return active;
+    }
+    let threads = inference_threads(state);
+    let gpu_layers = planned_gpu_layers(state, DEFAULT_MODEL).unwrap_or(0);
+    match (&state.backend, gpu_device()) {
+        (None, _) => {
+            let lang = i18n::ui_language(state);
+            let error = match &state.backend_error {
+                Some(detail) => format!("{} ({})", i18n::t(&lang, "backend_unavailable"), detail),
+                None => i18n::t(&lang, "backend_unavailable"),
+            };
+            ActiveBackend { kind: "none", device: None, gpu_layers: 0, threads: 0, error: Some(error) }
+        }
+        (Some(_), Some(device)) if gpu_layers > 0 => ActiveBackend { kind: "gpu", device: Some(device), gpu_layers, threads, error: None },
+        (Some(_), _) => ActiveBackend { kind: "cpu", device: None, gpu_layers: 0, threads, error: None },
+    }
+}
+
+/// Emitted as `mia-loading-progress` with the fraction of the tensors
+/// llama.cpp has read, from the progress callback of the model parameters.
+#[derive(Serialize, Clone)]
+struct LoadProgress {
+    percent: u8,
+}
+
+/// llama.cpp's load-progress callback; `user_data` points at the
+/// `&dyn Fn(f32)` given to `load_model`. A panic must not unwind into C, so
+/// it cancels the load instead.
+unsafe extern "C" fn on_load_progress(progress: f32, user_data: *mut c_void) -> bool {
+    let report = unsafe { &*(user_data as *const &dyn Fn(f32)) };
+    catch_unwind(AssertUnwindSafe(|| report(progress))).is_ok()
+}
+
+/// Loads a model file with `report` called as the tensors are read.
+/// llama-cpp-2's `LlamaModelParams` has no progress callback, so the model is
+/// loaded through llama-cpp-sys-2 and then handed to llama-cpp-2, which frees
+/// it on drop as usual.
+fn load_model(path: &Path, gpu_layers: u32, report: &dyn Fn(f32)) -> Result<LlamaModel, String> {
+    let c_path = CString::new(path.to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+    let mut params = unsafe { llama_cpp_sys_2::llama_model_default_params() };
+    params.n_gpu_layers = gpu_layers as i32;
+    params.progress_callback = Some(on_load_progress);
+    params.progress_callback_user_data = &report as *const &dyn Fn(f32) as *mut c_void;
+    // `report` outlives the call; llama.cpp keeps no reference to it afterwards.
+    let model = unsafe { llama_cpp_sys_2::llama_model_load_from_file(c_path.as_ptr(), params) };
+    let model = NonNull::new(model).ok_or_else(|| format!("{}: llama.cpp could not load the model", path.display()))?;
+    // SAFETY: `LlamaModel` is a `#[repr(transparent)]` wrapper around the
+    // `NonNull<llama_model>` that `LlamaModel::load_from_file` itself creates.
+    Ok(unsafe { std::mem::transmute::<NonNull<llama_cpp_sys_2::llama_model>, LlamaModel>(model) })
+}
+
+fn load_vision(name: &str, model: &LlamaModel) -> Option<MtmdContext> {
+    let path = mmproj_path(name);
+    if !path.exists() {
+        return None;
+    }
+    match MtmdContext::init_from_file(&path.to_string_lossy(), model, &MtmdContextParams::default()) {
+        Ok(vision) => {
+            println!(">>> Látás modul betöltve: {}", path.display());
+            Some(vision)
+        }
+        Err(e) => {
+            println!(">>> Látás modul betöltése sikertelen: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Moves a swapped-out model to the front of the resident list and drops
+/// whatever no longer fits in `residentModels`.
+fn park(state: &AppState, model: MiaModel) {
+    let keep = state.settings.lock().unwrap().resident_models.saturating_sub(1) as usize;
+    let evicted = {
+        let mut loader = state.model_loader.lock().unwrap();
+        loader.resident.insert(0, model);
+        let keep = keep.min(loader.resident.len());
+        loader.resident.split_off(keep)
+    };
+    for model in evicted {
+        println!(">>> Modell felszabadítva: {}", model.name);
+    }
+}
+
+/// Drops resident models, least recently used first, until `size_mb` fits in
+/// the free VRAM (RAM without a GPU) next to the headroom.
+fn make_room(state: &AppState, size_mb: u64) {
+    loop {
+        let (ram, vram) = free_memory_mb(state);
+        if vram.unwrap_or(ram) >= size_mb + VRAM_HEADROOM_MB {
+            return;
+        }
+        let Some(model) = state.model_loader.lock().unwrap().resident.pop() else { return };
+        println!(">>> Modell felszabadítva helyhiány miatt: {}", model.name);
+    }
+}
+
+/// Loads the `draftModel` setting next to the main model `name`. Skipped when
+/// it is unset, is the main model itself, or has a different vocabulary.
+fn load_draft(state: &AppState, backend: &LlamaBackend, name: &str, model: &LlamaModel) -> Option<DraftModel> {
+    let (draft_name, max_tokens) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.draft_model.clone()?, settings.draft_tokens)
+    };
+    if draft_name == name {
+        return None;
+    }
+    // Sized against the VRAM the main model left free.
+    let gpu_layers = planned_gpu_layers(state, &draft_name).unwrap_or(0);
+    let path = model_path(&draft_name);
+    let load = |gpu_layers: u32| {
+        let params = LlamaModelParams::default().with_n_gpu_layers(gpu_layers);
+        LlamaModel::load_from_file(backend, &path, &params)
+    };
+    let draft = match load(gpu_layers).or_else(|_| load(0)) {
+        Ok(draft) => draft,
+        Err(e) => {
+            println!(">>> Draft modell betöltése sikertelen ({}): {}", draft_name, e);
+            return None;
+        }
+    };
+    if draft.n_vocab() != model.n_vocab() {
+        println!(">>> A draft modell szótára eltér ({}), spekulatív dekódolás kikapcsolva", draft_name);
+        return None;
+    }
+    println!(">>> Draft modell betöltve: {} ({} token előre)", draft_name, max_tokens);
+    Some(DraftModel { model: draft, name: draft_name, max_tokens: max_tokens.max(1) as usize })
+}
+
+/// Makes `model` the active one.
+fn install(state: &AppState, model: MiaModel) {
+    {
+        let mut loader = state.model_loader.lock().unwrap();
+        loader.active = Some(model.runs_on.clone());
+        loader.info = Some(model.info.clone());
+    }
+    *state.mia_brain.lock().unwrap() = Some(model);
+}
+
+/// Makes `name` the active model on the calling (blocking) thread. The
+/// current model is parked first (see `park`), and a resident model is
+/// swapped back in without touching the disk. Otherwise the file is loaded
+/// with llama.cpp's progress reported as `mia-loading-progress`, offloading
+/// `planned_gpu_layers` to the GPU and fewer after every failed attempt
+/// (`fewer_gpu_layers`), down to CPU-only inference.
+fn load_blocking(handle: &AppHandle, name: &str) -> Result<(), String> {
+    let state = handle.state::<AppState>();
+    let waiting_since = Instant::now();
+    loop {
+        // Waits for a generation on the current model to finish.
+        let mut brain = state.mia_brain.lock().unwrap();
+        if brain.as_ref().is_some_and(|brain| brain.name == name) {
+            return Ok(());
+        }
+        // Requests already waiting for the current model go first, so chats
+        // on different models take turns instead of swapping on every step.
+        let pinned = brain.as_ref().is_some_and(|brain| is_pinned(&state, &brain.name));
+        if !pinned || waiting_since.elapsed() >= MAX_PIN_WAIT {
+            if let Some(current) = brain.take() {
+                park(&state, current);
+            }
+            break;
+        }
+        drop(brain);
+        thread::sleep(PIN_POLL_INTERVAL);
+    }
+    let resident = {
+        let mut loader = state.model_loader.lock().unwrap();
+        let index = loader.resident.iter().position(|model| model.name == name);
+        index.map(|index| loader.resident.remove(index))
+    };
+    if let Some(model) = resident {
+        println!(">>> Modell váltás a memóriából: {}", name);
+        install(&state, model);
+        return Ok(());
+    }
+
+    let backend = state.backend.clone().ok_or_else(|| i18n::t(&i18n::ui_language(&state), "backend_unavailable"))?;
+    let path = model_path(name);
+    let size = fs::metadata(&path).map_err(|e| format!("{}: {}", path.display(), e))?.len();
+    make_room(&state, size / (1024 * 1024));
+    let mut gpu_layers = planned_gpu_layers(&state, name)?;
+
+    let start = Instant::now();
+    let device = gpu_device();
+    let threads = inference_threads(&state);
+    // Emitted on every whole percent; llama.cpp calls back far more often.
+    let last_percent = Cell::new(None);
+    let report = |progress: f32| {
+        let percent = (progress.clamp(0.0, 1.0) * 100.0) as u8;
+        if last_percent.replace(Some(percent)) != Some(percent) {
+            let _ = handle.emit("mia-loading-progress", LoadProgress { percent });
+        }
+    };
+    let load = |gpu_layers: u32| {
+        last_percent.set(None);
+        load_model(&path, gpu_layers, &report)
+    };
+    let loaded = loop {
+        match load(gpu_layers) {
+            Err(e) if gpu_layers > 0 => {
+                let fewer = fewer_gpu_layers(&state, name, gpu_layers);
+                println!(">>> Betöltés {} GPU réteggel sikertelen ({}), újra {} réteggel", gpu_layers, e, fewer);
+                gpu_layers = fewer;
+            }
+            result => break result,
+        }
+    };
+    let model = loaded?;
+
+    let elapsed = start.elapsed().as_secs_f64();
+    let runs_on = if gpu_layers > 0 {
+        ActiveBackend { kind: "gpu", device, gpu_layers, threads, error: None }
+    } else {
+        ActiveBackend { kind: "cpu", device: None, gpu_layers: 0, threads, error: None }
+    };
+    println!(">>> Mia agya betöltve: {} ({:.1} mp, {}, {} GPU réteg, {} szál)", name, elapsed, runs_on.kind, gpu_layers, threads);
+    let vision = load_vision(name, &model);
+    let draft = load_draft(&state, &backend, name, &model);
+    let mut info = model_info(&path, &model, vision.is_some(), elapsed, gpu_layers);
+    info.draft_model = draft.as_ref().map(|draft| draft.name.clone());
+    install(&state, MiaModel { vision, model, draft, backend, threads, name: name.to_string(), info, runs_on });
+    Ok(())
+}
+
+fn is_active(state: &AppState, name: &str) -> bool {
+    state.mia_brain.lock().unwrap().as_ref().is_some_and(|brain| brain.name == name)
+}
+
+/// Makes `name` the active model unless it already is. Callers arriving while
+/// it is being loaded wait for that load instead of starting another; a load
+/// of a different model is waited out first. Emits `mia-loading-status`,
+/// `mia-loading-progress` and, on failure, `mia-loading-error`.
+pub async fn ensure_loaded(handle: &AppHandle, name: &str) -> Result<(), String> {
+    let state = handle.state::<AppState>();
+    state.model_loader.lock().unwrap().last_used = Instant::now();
+    loop {
+        if is_active(&state, name) {
+            return Ok(());
+        }
+
+        let (mut pending, ours) = {
+            let mut loader = state.model_loader.lock().unwrap();
+            match &loader.pending {
+                Some((loading, pending)) => (pending.clone(), loading == name),
+                None => {
+                    let (tx, rx) = watch::channel(None);
+                    loader.pending = Some((name.to_string(), rx.clone()));
+                    start_load(handle.clone(), name.to_string(), tx);
+                    (rx, true)
+                }
+            }
+        };
+
+        let result = pending.wait_for(Option::is_some).await.map_err(|e| e.to_string())?.clone();
+        if ours {
+            return result.unwrap_or(Ok(()));
+        }
+    }
+}
+
+/// Marks a model as wanted by a request in `lock_model` until dropped.
+struct Pin<'a> {
+    state: &'a AppState,
+    name: &'a str,
+}
+
+impl<'a> Pin<'a> {
+    fn new(state: &'a AppState, name: &'a str) -> Self {
+        *state.model_loader.lock().unwrap().pins.entry(name.to_string()).or_default() += 1;
+        Self { state, name }
+    }
+}
+
+impl Drop for Pin<'_> {
+    fn drop(&mut self) {
+        let mut loader = self.state.model_loader.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = loader.pins.get_mut(self.name) {
+            *count -= 1;
+            if *count == 0 {
+                loader.pins.remove(self.name);
+            }
+        }
+    }
+}
+
+fn is_pinned(state: &AppState, name: &str) -> bool {
+    state.model_loader.lock().unwrap().pins.contains_key(name)
+}
+
+/// Makes `name` the active model and locks it for generation. The model is
+/// pinned meanwhile: a load of another model waits until this request has
+/// the lock (see `load_blocking`), so it is not swapped out in between.
+pub async fn lock_model<'a>(handle: &AppHandle, state: &'a AppState, name: &str) -> Result<MutexGuard<'a, Option<MiaModel>>, String> {
+    let _pin = Pin::new(state, name);
+    loop {
+        ensure_loaded(handle, name).await?;
+        let brain = state.mia_brain.lock().unwrap();
+        if brain.as_ref().is_some_and(|brain| brain.name == name) {
+            return Ok(brain);
+        }
+    }
+}
+
+/// Starts loading without waiting for it, e.g. when the window opens.
+pub fn preload(handle: &AppHandle, name: String) {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = ensure_loaded(&handle, &name).await;
+    });
+}
+
+/// Ends a load in `start_load`, also when it panics: clears
+/// `ModelLoader::pending` so the next request starts a new load, and turns
+/// off the UI's loading state.
+struct PendingLoad<'a>(&'a AppHandle);
+
+impl Drop for PendingLoad<'_> {
+    fn drop(&mut self) {
+        let _ = self.0.emit("mia-loading-status", false);
+        let state = self.0.state::<AppState>();
+        state.model_loader.lock().unwrap_or_else(PoisonError::into_inner).pending = None;
+    }
+}
+
+fn start_load(handle: AppHandle, name: String, tx: watch::Sender<LoadResult>) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let pending = PendingLoad(&handle);
+        let _ = handle.emit("mia-loading-status", true);
+        let _ = handle.emit("mia-loading-progress", LoadProgress { percent: 0 });
+
+        let result = load_blocking(&handle, &name);
+        match &result {
+            Ok(()) => {
+                let _ = handle.emit("mia-loading-progress", LoadProgress { percent: 100 });
+            }
+            Err(e) => {
+                println!(">>> Mia agyának betöltése sikertelen: {}", e);
+                let _ = handle.emit("mia-loading-error", e.clone());
+            }
+        }
+        // Waiters woken by the result must not find this load still pending.
+        drop(pending);
+        let _ = tx.send(Some(result));
+    });
+}
+
+#[derive(Serialize, Clone)]
+struct Released {
+    reason: &'static str,
+}
+
+/// Drops the active and the resident models, freeing their VRAM, and tells
+/// the UI why.
+pub fn release(handle: &AppHandle, reason: &'static str) {
+    let state = handle.state::<AppState>();
+    let old_brain = state.mia_brain.lock().unwrap().take();
+    let resident = std::mem::take(&mut state.model_loader.lock().unwrap().resident);
+    if old_brain.is_none() && resident.is_empty() {
+        return;
+    }
+    drop(old_brain);
+    drop(resident);
+    let _ = handle.emit("mia-loading-status", false);
+    let _ = handle.emit("mia-model-released", Released { reason });
+    println!(">>> Mia agya felszabadítva ({}), VRAM visszaadva.", reason);
+}
+
+/// The chat window was hidden: releases right away under `Immediate`,
+/// otherwise starts the idle clock.
+pub fn on_window_hidden(handle: &AppHandle) {
+    let state = handle.state::<AppState>();
+    state.model_loader.lock().unwrap().last_used = Instant::now();
+    if state.settings.lock().unwrap().release_policy == ReleasePolicy::Immediate {
+        release(handle, "hidden");
+    }
+}
+
+/// A watched game was started: the game needs the VRAM now, so every policy
+/// but `KeepResident` releases right away.
+pub fn on_game_started(handle: &AppHandle) {
+    let policy = handle.state::<AppState>().settings.lock().unwrap().release_policy;
+    if policy != ReleasePolicy::KeepResident {
+        release(handle, "game");
+    }
+}
+
+/// The least free VRAM among the GPUs llama.cpp reports, in MB.
+fn free_vram_mb() -> Option<u64> {
+    llama_cpp_2::list_llama_ggml_backend_devices()
+        .into_iter()
+        .filter(|device| device.device_type == LlamaBackendDeviceType::Gpu)
+        .map(|device| device.memory_free as u64 / (1024 * 1024))
+        .min()
+}
+
+/// Free system RAM and, when llama.cpp reports a GPU, free VRAM, in MB.
+pub fn free_memory_mb(state: &AppState) -> (u64, Option<u64>) {
+    let ram = {
+        let mut sys = state.sys.lock().unwrap();
+        sys.refresh_memory();
+        sys.available_memory() / (1024 * 1024)
+    };
+    (ram, free_vram_mb())
+}
+
+/// Background check for the idle and low-memory rules.
+pub fn watch_release(handle: &AppHandle) {
+    let handle = handle.clone();
+    thread::spawn(move || loop {
+        thread::sleep(RELEASE_CHECK_INTERVAL);
+        let state = handle.state::<AppState>();
+        if state.mia_brain.lock().unwrap().is_none() {
+            continue;
+        }
+        let (policy, idle_minutes, min_free_mb) = {
+            let settings = state.settings.lock().unwrap();
+            (settings.release_policy, settings.release_idle_minutes, settings.release_min_free_mb)
+        };
+
+        match policy {
+            ReleasePolicy::AfterIdle => {
+                let visible = handle.get_webview_window("main").is_some_and(|w| w.is_visible().unwrap_or(false));
+                let idle = state.model_loader.lock().unwrap().last_used.elapsed();
+                if !visible && idle >= Duration::from_secs(idle_minutes * 60) {
+                    release(&handle, "idle");
+                }
+            }
+            ReleasePolicy::OnPressure => {
+                let (ram, vram) = free_memory_mb(&state);
+                if ram < min_free_mb || vram.is_some_and(|vram| vram < min_free_mb) {
+                    release(&handle, "memory");
+                }
+            }
+            ReleasePolicy::Immediate | ReleasePolicy::KeepResident => {}
+        }
+    });
+}
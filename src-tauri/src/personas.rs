@@ -0,0 +1,259 @@
+use crate::auto_mode::AutoModeRules;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const BASIC_ID: &str = "basic";
+pub const PHILOSOPHY_ID: &str = "philosophy";
+pub const SEARCH_ID: &str = "search";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Mirostat {
+    #[default]
+    Off,
+    V1,
+    V2,
+}
+
+/// Sampler chain settings of a persona. Fields missing from older
+/// `personas.json` files take their defaults; neutral values (penalties of
+/// 1.0 / 0.0, `minP` 0.0, `typicalP` 1.0, `dryMultiplier` 0.0) leave a stage
+/// out. The defaults are all neutral, so internal tasks (titles, structured
+/// output, documents) sample plainly and personas opt in via [`chat_sampling`].
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SamplingParams {
+    pub temperature: f32,
+    #[serde(rename = "topK")]
+    pub top_k: i32,
+    #[serde(rename = "topP")]
+    pub top_p: f32,
+    #[serde(rename = "minP")]
+    pub min_p: f32,
+    #[serde(rename = "typicalP")]
+    pub typical_p: f32,
+    #[serde(rename = "repeatPenalty")]
+    pub repeat_penalty: f32,
+    #[serde(rename = "frequencyPenalty")]
+    pub frequency_penalty: f32,
+    #[serde(rename = "presencePenalty")]
+    pub presence_penalty: f32,
+    /// Recent tokens the penalties look at.
+    #[serde(rename = "penaltyLastN")]
+    pub penalty_last_n: i32,
+    /// Replaces top-k / top-p / min-p / typical sampling when on.
+    pub mirostat: Mirostat,
+    #[serde(rename = "mirostatTau")]
+    pub mirostat_tau: f32,
+    #[serde(rename = "mirostatEta")]
+    pub mirostat_eta: f32,
+    /// DRY ("don't repeat yourself") penalty on repeated sequences.
+    #[serde(rename = "dryMultiplier")]
+    pub dry_multiplier: f32,
+    #[serde(rename = "dryBase")]
+    pub dry_base: f32,
+    #[serde(rename = "dryAllowedLength")]
+    pub dry_allowed_length: i32,
+    /// Recent tokens DRY looks for repetitions in; -1 is the whole context.
+    #[serde(rename = "dryLastN")]
+    pub dry_last_n: i32,
+    /// Fixed seed for reproducible answers; random when `None`.
+    pub seed: Option<u32>,
+}
+
+impl Default for SamplingParams {
+    fn default() -> Self {
+        Self {
+            temperature: 0.75,
+            top_k: 40,
+            top_p: 0.95,
+            min_p: 0.0,
+            typical_p: 1.0,
+            repeat_penalty: 1.0,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            penalty_last_n: 64,
+            mirostat: Mirostat::Off,
+            mirostat_tau: 5.0,
+            mirostat_eta: 0.1,
+            dry_multiplier: 0.0,
+            dry_base: 1.75,
+            dry_allowed_length: 2,
+            dry_last_n: -1,
+            seed: None,
+        }
+    }
+}
+
+/// Chat settings of the built-in personas: a light repetition penalty and
+/// min-p cut on top of the neutral defaults.
+fn chat_sampling(temperature: f32) -> SamplingParams {
+    SamplingParams { temperature, min_p: 0.05, repeat_penalty: 1.1, ..SamplingParams::default() }
+}
+
+/// A mode Mia can answer in. The built-in Basic/Philosophy/Search modes are
+/// personas too, shipped as defaults the user may edit but not delete.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Persona {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    #[serde(rename = "systemPrompt")]
+    pub system_prompt: String,
+    pub sampling: SamplingParams,
+    #[serde(rename = "webSearch")]
+    pub web_search: bool,
+    /// Auto-mode keywords per language code; the `"any"` list applies to every language.
+    /// A keyword ending in `*` matches as a word prefix (`auto_mode::classify`).
+    #[serde(rename = "triggerKeywords", deserialize_with = "deserialize_keywords")]
+    pub trigger_keywords: HashMap<String, Vec<String>>,
+    #[serde(rename = "builtIn", default)]
+    pub built_in: bool,
+    /// Model file in `models/` to answer with; the default model when `None`.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+pub const ANY_LANGUAGE: &str = "any";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeywordsRepr {
+    PerLanguage(HashMap<String, Vec<String>>),
+    Flat(Vec<String>),
+}
+
+/// Accepts both the per-language map and the older flat keyword list.
+fn deserialize_keywords<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, Vec<String>>, D::Error> {
+    Ok(match KeywordsRepr::deserialize(deserializer)? {
+        KeywordsRepr::PerLanguage(map) => map,
+        KeywordsRepr::Flat(list) => HashMap::from([(ANY_LANGUAGE.to_string(), list)]),
+    })
+}
+
+fn keywords(en: &[&str], hu: &[&str]) -> HashMap<String, Vec<String>> {
+    let to_vec = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    HashMap::from([("en".to_string(), to_vec(en)), ("hu".to_string(), to_vec(hu))])
+}
+
+impl Persona {
+    /// Keywords for the given language plus the language-independent ones.
+    /// Without a detected language every list is used.
+    pub fn keywords_for(&self, lang: Option<&str>) -> Vec<String> {
+        self.trigger_keywords.iter()
+            .filter(|(code, _)| lang.is_none_or(|l| code.as_str() == l || code.as_str() == ANY_LANGUAGE))
+            .flat_map(|(_, list)| list.iter().cloned())
+            .collect()
+    }
+}
+
+pub fn default_personas() -> Vec<Persona> {
+    vec![
+        Persona {
+            id: BASIC_ID.into(),
+            name: "Basic".into(),
+            icon: "💬".into(),
+            system_prompt: "You are Mia, a cute and smart AI assistant. Your goal is to be helpful and kind. Use a friendly tone and emojis.".into(),
+            sampling: chat_sampling(0.75),
+            web_search: false,
+            trigger_keywords: HashMap::new(),
+            built_in: true,
+            model: None,
+        },
+        Persona {
+            id: PHILOSOPHY_ID.into(),
+            name: "Philosophy".into(),
+            icon: "🦉".into(),
+            system_prompt: "You are Mia, in Philosopher Mode. Provide deep existential insights. Use poetic, serious language and challenge the user's perspective.".into(),
+            sampling: chat_sampling(1.25),
+            web_search: false,
+            trigger_keywords: keywords(
+                &["why", "meaning", "life", "death", "existence", "truth", "philosophy", "soul"],
+                &["miért", "élet*", "halál*", "értelm*", "világ*", "létezés*", "igazság*", "filozófi*"],
+            ),
+            built_in: true,
+            model: None,
+        },
+        Persona {
+            id: SEARCH_ID.into(),
+            name: "Search".into(),
+            icon: "🔎".into(),
+            system_prompt: "You are Mia, a Fact-Checking Assistant. Answer using the provided web context accurately. \
+                DO NOT include URLs or links in your response text. Provide ONLY the information. \
+                The sources will be displayed as separate buttons by the system.".into(),
+            sampling: chat_sampling(0.3),
+            web_search: true,
+            trigger_keywords: keywords(
+                &[
+                    "latest", "today", "yesterday", "news", "price", "cost", "weather", "score",
+                    "release", "current", "now", "this week", "stock", "who won",
+                ],
+                &["legújabb*", "ma", "tegnap", "hírek*", "ár", "árfolyam*", "időjárás*", "jelenleg", "eredmény*"],
+            ),
+            built_in: true,
+            model: None,
+        },
+    ]
+}
+
+/// Loads personas from disk, adding any built-in persona missing from the file.
+pub fn load_personas(path: &Path) -> Vec<Persona> {
+    let mut personas: Vec<Persona> = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    for default in default_personas() {
+        if !personas.iter().any(|p| p.id == default.id) {
+            personas.push(default);
+        }
+    }
+    personas
+}
+
+/// Moves the keyword lists older settings kept in `autoModeRules` into the
+/// Search and Philosophy personas. A list equal to the old defaults is dropped;
+/// an edited one replaces the persona's keywords for every language. Returns
+/// whether there was anything to migrate.
+pub fn migrate_legacy_keywords(personas: &mut [Persona], rules: &mut AutoModeRules) -> bool {
+    let lists = [
+        (SEARCH_ID, std::mem::take(&mut rules.legacy_search_keywords)),
+        (PHILOSOPHY_ID, std::mem::take(&mut rules.legacy_philosophy_keywords)),
+    ];
+    let defaults = default_personas();
+    let mut migrated = false;
+    for (id, list) in lists {
+        if list.is_empty() {
+            continue;
+        }
+        migrated = true;
+        let mut sorted = list.clone();
+        sorted.sort();
+        // The old lists had no `*` prefix markers.
+        let mut default_list: Vec<String> = find(&defaults, id)
+            .map(|p| p.keywords_for(None).iter().map(|k| k.trim_end_matches('*').to_string()).collect())
+            .unwrap_or_default();
+        default_list.sort();
+        if sorted == default_list {
+            continue;
+        }
+        if let Some(persona) = personas.iter_mut().find(|p| p.id == id) {
+            println!(">>> Régi Auto mód kulcsszavak áthelyezve: {}", persona.name);
+            persona.trigger_keywords = HashMap::from([(ANY_LANGUAGE.to_string(), list)]);
+        }
+    }
+    migrated
+}
+
+pub fn save_personas(path: &Path, personas: &[Persona]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(personas).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+pub fn find<'a>(personas: &'a [Persona], id: &str) -> Option<&'a Persona> {
+    personas.iter().find(|p| p.id == id)
+}
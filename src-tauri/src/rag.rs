@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const CHUNK_CHARS: usize = 1000;
+const CHUNK_OVERLAP: usize = 200;
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+/// Lowercased words of at least three bytes, the terms documents and chat
+/// messages are matched by.
+pub fn tokenize_for_match(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter_map(|w| {
+            let w = w.to_lowercase();
+            if w.len() >= 3 {
+                Some(w)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A retrievable piece of a document with the location it came from.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Chunk {
+    pub index: usize,
+    pub page: Option<u32>,
+    pub section: Option<String>,
+    pub text: String,
+    terms: HashMap<String, u32>,
+    len: u32,
+}
+
+/// Where an answer's information came from, shown to the user next to the reply.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Citation {
+    pub document: String,
+    pub page: Option<u32>,
+    pub section: Option<String>,
+}
+
+impl Citation {
+    pub fn label(&self) -> String {
+        match (&self.page, &self.section) {
+            (Some(page), _) => format!("{}, p. {}", self.document, page),
+            (None, Some(section)) => format!("{}, § {}", self.document, section),
+            (None, None) => self.document.clone(),
+        }
+    }
+}
+
+pub struct Hit<'a> {
+    pub document: &'a str,
+    pub chunk: &'a Chunk,
+    pub score: f32,
+}
+
+impl Hit<'_> {
+    pub fn citation(&self) -> Citation {
+        Citation {
+            document: self.document.to_string(),
+            page: self.chunk.page,
+            section: self.chunk.section.clone(),
+        }
+    }
+}
+
+fn make_chunk(index: usize, page: Option<u32>, section: Option<String>, text: String) -> Chunk {
+    let mut terms = HashMap::new();
+    let tokens = tokenize_for_match(&text);
+    for t in &tokens {
+        *terms.entry(t.clone()).or_insert(0) += 1;
+    }
+    Chunk { index, page, section, text, terms, len: tokens.len() as u32 }
+}
+
+/// Markdown headings, or short upper-case lines as they often appear in PDFs.
+fn heading_of(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    if let Some(h) = trimmed.strip_prefix('#') {
+        return Some(h.trim_start_matches('#').trim().to_string()).filter(|h| !h.is_empty());
+    }
+    let letters: Vec<char> = trimmed.chars().filter(|c| c.is_alphabetic()).collect();
+    if trimmed.len() <= 80 && letters.len() >= 3 && letters.iter().all(|c| c.is_uppercase()) {
+        return Some(trimmed.to_string());
+    }
+    None
+}
+
+/// The last `overlap` characters of `text`, starting at a word boundary.
+fn overlap_tail(text: &str, overlap: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= overlap {
+        return text.to_string();
+    }
+    let tail: String = chars[chars.len() - overlap..].iter().collect();
+    match tail.find(char::is_whitespace) {
+        Some(pos) => tail[pos..].trim_start().to_string(),
+        None => tail,
+    }
+}
+
+/// Cuts a line longer than `CHUNK_CHARS` into pieces of at most that many
+/// characters, breaking at the last whitespace in the second half of each
+/// piece when there is one.
+fn split_long_line(line: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.chars().count() > CHUNK_CHARS {
+        let limit = rest.char_indices().nth(CHUNK_CHARS).map_or(rest.len(), |(i, _)| i);
+        let cut = match rest[..limit].rfind(char::is_whitespace) {
+            Some(pos) if pos > limit / 2 => pos,
+            _ => limit,
+        };
+        pieces.push(&rest[..cut]);
+        rest = rest[cut..].trim_start();
+    }
+    pieces.push(rest);
+    pieces
+}
+
+/// Splits a document into overlapping chunks. Pages are separated by form
+/// feeds (`\x0c`); a chunk never spans two pages.
+pub fn chunk_document(text: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let has_pages = text.contains('\x0c');
+    let mut section: Option<String> = None;
+
+    for (page_idx, page_text) in text.split('\x0c').enumerate() {
+        let page = if has_pages { Some(page_idx as u32 + 1) } else { None };
+        let mut current = String::new();
+        let mut current_section = section.clone();
+
+        for line in page_text.lines() {
+            if let Some(heading) = heading_of(line) {
+                if current.trim().chars().count() > CHUNK_OVERLAP {
+                    chunks.push(make_chunk(chunks.len(), page, current_section.clone(), std::mem::take(&mut current)));
+                }
+                section = Some(heading);
+                current_section = section.clone();
+            }
+
+            for piece in split_long_line(line) {
+                current.push_str(piece);
+                current.push('\n');
+
+                if current.chars().count() >= CHUNK_CHARS {
+                    let tail = overlap_tail(&current, CHUNK_OVERLAP);
+                    chunks.push(make_chunk(chunks.len(), page, current_section.clone(), std::mem::replace(&mut current, tail)));
+                    current_section = section.clone();
+                }
+            }
+        }
+
+        if !current.trim().is_empty() {
+            chunks.push(make_chunk(chunks.len(), page, current_section, current));
+        }
+    }
+    chunks
+}
+
+/// Ranks the chunks of all given documents against the question with BM25.
+pub fn search<'a>(documents: &[(&'a str, &'a [Chunk])], question: &str, limit: usize) -> Vec<Hit<'a>> {
+    let query: HashSet<String> = tokenize_for_match(question).into_iter().collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let all: Vec<(&str, &Chunk)> = documents.iter()
+        .flat_map(|&(name, chunks)| chunks.iter().map(move |c| (name, c)))
+        .collect();
+    if all.is_empty() {
+        return Vec::new();
+    }
+
+    let n = all.len() as f32;
+    let avg_len = all.iter().map(|(_, c)| c.len as f32).sum::<f32>() / n;
+    let df: HashMap<&String, f32> = query.iter()
+        .map(|t| (t, all.iter().filter(|(_, c)| c.terms.contains_key(t)).count() as f32))
+        .collect();
+
+    let mut hits: Vec<Hit> = all.into_iter()
+        .map(|(document, chunk)| {
+            let score = query.iter()
+                .map(|t| {
+                    let tf = *chunk.terms.get(t).unwrap_or(&0) as f32;
+                    if tf == 0.0 {
+                        return 0.0;
+                    }
+                    let df = df[t];
+                    let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+                    let norm = 1.0 - BM25_B + BM25_B * chunk.len as f32 / avg_len.max(1.0);
+                    idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm)
+                })
+                .sum::<f32>();
+            Hit { document, chunk, score }
+        })
+        .filter(|h| h.score > 0.0)
+        .collect();
+
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    hits.truncate(limit);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_stay_on_their_page() {
+        let chunks = chunk_document("first page\x0csecond page\x0c\x0cfourth page");
+        let pages: Vec<_> = chunks.iter().map(|c| (c.page, c.text.trim())).collect();
+        assert_eq!(pages, vec![(Some(1), "first page"), (Some(2), "second page"), (Some(4), "fourth page")]);
+        assert!(chunk_document("no pages").iter().all(|c| c.page.is_none()));
+    }
+
+    #[test]
+    fn headings_start_sections() {
+        let body = "word ".repeat(60);
+        let text = format!("# Intro\n{}\n## Usage\n{}\nSUMMARY\nshort", body, body);
+        let chunks = chunk_document(&text);
+        let sections: Vec<_> = chunks.iter().map(|c| c.section.as_deref()).collect();
+        assert_eq!(sections, vec![Some("Intro"), Some("Usage"), Some("SUMMARY")]);
+        assert!(chunks[0].text.starts_with("# Intro\n"));
+        assert_eq!(chunks[2].text, "SUMMARY\nshort\n");
+    }
+
+    #[test]
+    fn long_text_is_split_with_overlap() {
+        let line = |i: usize| (0..8).map(|j| format!("word{}", i * 8 + j)).collect::<Vec<_>>().join(" ");
+        let text = (0..60).map(line).collect::<Vec<_>>().join("\n");
+        let chunks = chunk_document(&text);
+        assert!(chunks.len() > 1);
+        // A chunk is closed by the line that takes it past `CHUNK_CHARS`.
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() < CHUNK_CHARS + line(59).len() + 1);
+        }
+        let first_words: Vec<&str> = chunks[0].text.split_whitespace().collect();
+        let second_start = chunks[1].text.split_whitespace().next().unwrap();
+        assert!(first_words.contains(&second_start));
+        assert_eq!(chunks.iter().map(|c| c.index).collect::<Vec<_>>(), (0..chunks.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn line_without_spaces_is_hard_split() {
+        let text = "x".repeat(CHUNK_CHARS * 3);
+        let pieces = split_long_line(&text);
+        assert_eq!(pieces.len(), 3);
+        assert!(pieces.iter().all(|p| p.chars().count() == CHUNK_CHARS));
+    }
+
+    #[test]
+    fn search_ranks_matching_chunk_first() {
+        let a = chunk_document("The reactor cooling system uses water pumps.");
+        let b = chunk_document("Chocolate cake recipe with flour and sugar.");
+        let hits = search(&[("a.txt", &a), ("b.txt", &b)], "how does cooling work in the reactor", 5);
+        assert_eq!(hits.first().map(|h| h.document), Some("a.txt"));
+    }
+}
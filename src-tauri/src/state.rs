@@ -1,16 +1,52 @@
 use llama_cpp_2::llama_backend::LlamaBackend;
 use llama_cpp_2::model::LlamaModel;
+use llama_cpp_2::mtmd::MtmdContext;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use sysinfo::System;
 use std::collections::HashMap;
+use notify::RecommendedWatcher;
+use crate::attachments::AttachmentStore;
+use crate::auto_mode::AutoModeRules;
+use crate::extract::{ExtractorRegistry, TruncationStrategy};
+use crate::knowledge::KnowledgeStore;
+use crate::model_manager::{ActiveBackend, ModelInfo, ModelLoader, ReleasePolicy};
+use crate::personas::{self, Persona};
+use crate::rag::Citation;
+use crate::web_cache::WebCache;
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug, Default)]
 pub enum MiaMode {
+    #[default]
     Auto,
     Basic,
     Philosophy,
-    Search
+    Search,
+    Persona(String),
+}
+
+impl MiaMode {
+    /// The persona backing this mode; `None` for Auto, which picks one per message.
+    pub fn persona_id(&self) -> Option<String> {
+        match self {
+            MiaMode::Auto => None,
+            MiaMode::Basic => Some(personas::BASIC_ID.into()),
+            MiaMode::Philosophy => Some(personas::PHILOSOPHY_ID.into()),
+            MiaMode::Search => Some(personas::SEARCH_ID.into()),
+            MiaMode::Persona(id) => Some(id.clone()),
+        }
+    }
+
+    pub fn from_persona_id(id: &str) -> Self {
+        match id {
+            personas::BASIC_ID => MiaMode::Basic,
+            personas::PHILOSOPHY_ID => MiaMode::Philosophy,
+            personas::SEARCH_ID => MiaMode::Search,
+            other => MiaMode::Persona(other.to_string()),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -24,7 +60,31 @@ pub struct ChatMessage {
     pub role: String,
     pub content: String,
     pub timestamp: u64,
-    pub sources: Option<Vec<WebSource>>
+    pub sources: Option<Vec<WebSource>>,
+    /// Ids of files attached to this message, see `attachments.rs`.
+    pub attachments: Option<Vec<String>>,
+    /// Document locations the answer drew from, see `rag.rs`.
+    pub citations: Option<Vec<Citation>>,
+}
+
+/// A conversation together with the mode it was last used in.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Chat {
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub mode: MiaMode,
+    /// Knowledge bases (indexed directories) searched when answering in this chat.
+    #[serde(rename = "knowledgeBases", default)]
+    pub knowledge_bases: Vec<String>,
+    /// Model file in `models/` for this chat, overriding the persona's.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Generated by `generate_chat_title`; the sidebar falls back to the first message.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Language last detected from the user in this chat, see `i18n::reply_language`.
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -34,10 +94,91 @@ pub struct AppSettings {
     pub searxng_url: String,
     #[serde(rename = "launchOnStartup")]
     pub launch_on_startup: bool,
+    #[serde(rename = "offlineMode", default)]
+    pub offline_mode: bool,
+    #[serde(rename = "webCacheTtlMinutes", default = "default_web_cache_ttl")]
+    pub web_cache_ttl_minutes: u64,
+    #[serde(rename = "webCacheMaxMb", default = "default_web_cache_max_mb")]
+    pub web_cache_max_mb: u64,
+    /// Also download the top search result and give its text to the model.
+    #[serde(rename = "fetchTopResult", default)]
+    pub fetch_top_result: bool,
+    #[serde(rename = "autoModeRules", default)]
+    pub auto_mode_rules: AutoModeRules,
+    /// "auto" to follow the user's language, or a locale code such as "en" / "hu".
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Larger text uploads are cut according to `upload_truncation`, other files are rejected.
+    #[serde(rename = "maxUploadMb", default = "default_max_upload_mb")]
+    pub max_upload_mb: u64,
+    #[serde(rename = "uploadTruncation", default)]
+    pub upload_truncation: TruncationStrategy,
+    /// Global shortcut that opens the chat pre-filled with the clipboard; empty disables it.
+    #[serde(rename = "clipboardShortcut", default = "default_clipboard_shortcut")]
+    pub clipboard_shortcut: String,
+    /// When the model is unloaded to free VRAM, see `ReleasePolicy`.
+    #[serde(rename = "releasePolicy", default)]
+    pub release_policy: ReleasePolicy,
+    #[serde(rename = "releaseIdleMinutes", default = "default_release_idle_minutes")]
+    pub release_idle_minutes: u64,
+    /// Free RAM / VRAM below which `OnPressure` unloads the model.
+    #[serde(rename = "releaseMinFreeMb", default = "default_release_min_free_mb")]
+    pub release_min_free_mb: u64,
+    /// Inference threads; 0 picks them from the CPU core count.
+    #[serde(rename = "cpuThreads", default)]
+    pub cpu_threads: u32,
+    /// Layers offloaded to the GPU; `None` sizes them from the model and free VRAM.
+    #[serde(rename = "gpuLayers", default)]
+    pub gpu_layers: Option<u32>,
+    /// Models kept in memory at once, the active one included; swapping back
+    /// to a resident model skips the load.
+    #[serde(rename = "residentModels", default = "default_resident_models")]
+    pub resident_models: u32,
+    /// Hugging Face-compatible host `download_model` fetches from.
+    #[serde(rename = "modelRepositoryUrl", default = "default_model_repository_url")]
+    pub model_repository_url: String,
+    /// Small model in `models/` that drafts tokens for speculative decoding;
+    /// it must share the main model's vocabulary. Applies from the next load.
+    #[serde(rename = "draftModel", default)]
+    pub draft_model: Option<String>,
+    /// Tokens drafted ahead per verification step.
+    #[serde(rename = "draftTokens", default = "default_draft_tokens")]
+    pub draft_tokens: u32,
+}
+
+fn default_web_cache_ttl() -> u64 { 360 }
+fn default_web_cache_max_mb() -> u64 { 50 }
+fn default_language() -> String { "auto".into() }
+fn default_max_upload_mb() -> u64 { 20 }
+fn default_clipboard_shortcut() -> String { "CommandOrControl+Shift+Space".into() }
+fn default_release_idle_minutes() -> u64 { 10 }
+fn default_release_min_free_mb() -> u64 { 1024 }
+fn default_resident_models() -> u32 { 1 }
+fn default_model_repository_url() -> String { "https://huggingface.co".into() }
+fn default_draft_tokens() -> u32 { 4 }
+
+pub struct DraftModel {
+    pub model: LlamaModel,
+    pub name: String,
+    /// Tokens proposed per step (`draftTokens`).
+    pub max_tokens: usize,
 }
 
 pub struct MiaModel {
+    /// Vision projector (mmproj) for image input, when one is installed next to
+    /// the model. Declared first so it is dropped before the model it wraps.
+    pub vision: Option<MtmdContext>,
     pub model: LlamaModel,
+    /// Draft model for speculative decoding in `ask_mia`, when configured.
+    pub draft: Option<DraftModel>,
+    /// Kept alive for as long as the model; contexts are created from it.
+    pub backend: Arc<LlamaBackend>,
+    /// Threads used for generation and for prompt processing.
+    pub threads: i32,
+    /// File name in `models/`.
+    pub name: String,
+    pub info: ModelInfo,
+    pub runs_on: ActiveBackend,
 }
 
 impl Default for AppSettings {
@@ -46,6 +187,24 @@ impl Default for AppSettings {
             games: vec!["cs2.exe".into(), "valorant.exe".into()],
             searxng_url: "https://searx.example.com".into(),
             launch_on_startup: true,
+            offline_mode: false,
+            web_cache_ttl_minutes: default_web_cache_ttl(),
+            web_cache_max_mb: default_web_cache_max_mb(),
+            fetch_top_result: false,
+            auto_mode_rules: AutoModeRules::default(),
+            language: default_language(),
+            max_upload_mb: default_max_upload_mb(),
+            upload_truncation: TruncationStrategy::default(),
+            clipboard_shortcut: default_clipboard_shortcut(),
+            release_policy: ReleasePolicy::default(),
+            release_idle_minutes: default_release_idle_minutes(),
+            release_min_free_mb: default_release_min_free_mb(),
+            cpu_threads: 0,
+            gpu_layers: None,
+            resident_models: default_resident_models(),
+            model_repository_url: default_model_repository_url(),
+            draft_model: None,
+            draft_tokens: default_draft_tokens(),
         }
     }
 }
@@ -55,27 +214,30 @@ pub struct AppState {
     pub games_list: Arc<Mutex<Vec<String>>>,
     pub sys: Arc<Mutex<System>>,
     pub mia_brain: Arc<Mutex<Option<MiaModel>>>,
-    pub backend: Arc<LlamaBackend>,
-    pub chats: Mutex<HashMap<String, Vec<ChatMessage>>>,
+    pub model_loader: Mutex<ModelLoader>,
+    /// `None` when llama.cpp could not be initialized; the app runs without a model.
+    pub backend: Option<Arc<LlamaBackend>>,
+    /// Why `LlamaBackend::init` failed, when `backend` is `None`; reported
+    /// by `get_system_stats`.
+    pub backend_error: Option<String>,
+    pub chats: Mutex<HashMap<String, Chat>>,
     pub active_chat_id: Mutex<String>,
-    pub current_mode: Mutex<MiaMode>
+    pub settings: Mutex<AppSettings>,
+    pub web_cache: Mutex<WebCache>,
+    pub personas: Mutex<Vec<Persona>>,
+    /// Language of the active chat; backend messages outside a chat use it
+    /// when `language` is "auto".
+    pub active_chat_language: Mutex<String>,
+    pub attachments: Mutex<AttachmentStore>,
+    pub extractors: ExtractorRegistry,
+    pub knowledge: Mutex<KnowledgeStore>,
+    pub knowledge_watchers: Mutex<HashMap<String, RecommendedWatcher>>,
+    /// Cancel flags of the running model downloads, by file name.
+    pub downloads: Mutex<HashMap<String, Arc<AtomicBool>>>,
 }
 
-impl AppState {
-    #[allow(dead_code)]
-    pub fn new() -> Self {
-        let backend = LlamaBackend::init().expect("Llama backend init sikertelen!");
-
-        println!("Llama backend inicializálva");
-
-        Self {
-            games_list: Arc::new(Mutex::new(Vec::new())),
-            sys: Arc::new(Mutex::new(System::new_all())),
-            mia_brain: Arc::new(Mutex::new(None)),
-            backend: Arc::new(backend),
-            chats: Mutex::new(HashMap::new()),
-            active_chat_id: Mutex::new(String::new()),
-            current_mode: Mutex::new(MiaMode::Auto)
-        }
-    }
+/// Milliseconds since the Unix epoch, the timestamps of messages, attachments
+/// and knowledge bases.
+pub fn get_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
 }
\ No newline at end of file
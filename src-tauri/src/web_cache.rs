@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::state::WebSource;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CachedSearch {
+    pub context: String,
+    pub sources: Vec<WebSource>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum CachedValue {
+    Search(CachedSearch),
+    Page { text: String },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CacheEntry {
+    pub value: CachedValue,
+    pub created_at: u64,
+    pub last_access: u64,
+    pub size: u64,
+}
+
+/// Writes of new entries are batched: the file is rewritten at most this often.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Disk-backed cache for web search results and fetched pages.
+/// Entries expire after the configured TTL; when the total size exceeds the
+/// limit the least recently used entries are evicted first.
+#[derive(Default)]
+pub struct WebCache {
+    entries: HashMap<String, CacheEntry>,
+    path: Option<PathBuf>,
+    /// Entries added or read since the last save, so access times survive a restart.
+    dirty: bool,
+    last_save: Option<Instant>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn search_key(provider: &str, query: &str) -> String {
+    format!("search:{}:{}", provider, normalize(query))
+}
+
+pub fn page_key(url: &str) -> String {
+    format!("page:{}", url.trim().trim_end_matches('/'))
+}
+
+impl WebCache {
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { entries, path: Some(path), ..Self::default() }
+    }
+
+    /// Returns a fresh entry, or a stale one too when `allow_stale` is set (offline mode).
+    pub fn get(&mut self, key: &str, ttl_secs: u64, allow_stale: bool) -> Option<CachedValue> {
+        let now = now_secs();
+        let entry = self.entries.get_mut(key)?;
+        if !allow_stale && now.saturating_sub(entry.created_at) > ttl_secs {
+            return None;
+        }
+        entry.last_access = now;
+        self.dirty = true;
+        Some(entry.value.clone())
+    }
+
+    pub fn put(&mut self, key: String, value: CachedValue, max_bytes: u64) {
+        let size = serde_json::to_vec(&value).map(|v| v.len() as u64).unwrap_or(0);
+        if size > max_bytes {
+            return;
+        }
+        let now = now_secs();
+        self.entries.insert(key, CacheEntry { value, created_at: now, last_access: now, size });
+        self.evict(max_bytes);
+        self.dirty = true;
+        if self.last_save.is_none_or(|at| at.elapsed() >= SAVE_INTERVAL) {
+            let _ = self.flush();
+        }
+    }
+
+    /// Saves changes made since the last save; called on exit.
+    pub fn flush(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.entries.clear();
+        self.save()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.entries.values().map(|e| e.size).sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn evict(&mut self, max_bytes: u64) {
+        let mut total = self.total_size();
+        if total <= max_bytes {
+            return;
+        }
+        let mut by_age: Vec<(String, u64, u64)> = self.entries.iter()
+            .map(|(k, e)| (k.clone(), e.last_access, e.size))
+            .collect();
+        by_age.sort_by_key(|(_, last_access, _)| *last_access);
+
+        for (key, _, size) in by_age {
+            if total <= max_bytes {
+                break;
+            }
+            self.entries.remove(&key);
+            total = total.saturating_sub(size);
+        }
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        self.last_save = Some(Instant::now());
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string(&self.entries).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(text: &str) -> CachedValue {
+        CachedValue::Page { text: text.to_string() }
+    }
+
+    fn text_of(value: Option<CachedValue>) -> Option<String> {
+        match value? {
+            CachedValue::Page { text } => Some(text),
+            CachedValue::Search(_) => None,
+        }
+    }
+
+    #[test]
+    fn expired_entries_only_served_when_stale_allowed() {
+        let mut cache = WebCache::default();
+        cache.put(page_key("https://example.com/"), page("hello"), 1024);
+        assert_eq!(text_of(cache.get("page:https://example.com", 60, false)).as_deref(), Some("hello"));
+
+        cache.entries.get_mut("page:https://example.com").unwrap().created_at -= 120;
+        assert!(cache.get("page:https://example.com", 60, false).is_none());
+        assert_eq!(text_of(cache.get("page:https://example.com", 60, true)).as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let mut cache = WebCache::default();
+        let size = serde_json::to_vec(&page("aaaa")).unwrap().len() as u64;
+        cache.put("a".into(), page("aaaa"), size * 3);
+        cache.put("b".into(), page("bbbb"), size * 3);
+        cache.put("c".into(), page("cccc"), size * 3);
+        cache.entries.get_mut("a").unwrap().last_access = 1;
+        cache.entries.get_mut("b").unwrap().last_access = 3;
+        cache.entries.get_mut("c").unwrap().last_access = 2;
+
+        cache.put("d".into(), page("dddd"), size * 3);
+        assert_eq!(cache.len(), 3);
+        assert!(!cache.entries.contains_key("a"));
+        assert!(cache.total_size() <= size * 3);
+
+        cache.put("e".into(), page("eeee"), size * 3);
+        assert!(!cache.entries.contains_key("c"));
+        assert!(cache.entries.contains_key("b"));
+    }
+
+    #[test]
+    fn hits_mark_the_cache_dirty() {
+        let mut cache = WebCache::default();
+        cache.put("a".into(), page("aaaa"), 1024);
+        cache.dirty = false;
+
+        assert!(cache.get("missing", 60, false).is_none());
+        assert!(!cache.dirty);
+        assert!(cache.get("a", 60, false).is_some());
+        assert!(cache.dirty);
+    }
+
+    #[test]
+    fn oversized_values_are_not_cached() {
+        let mut cache = WebCache::default();
+        cache.put("big".into(), page(&"x".repeat(100)), 10);
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn keys_are_normalized() {
+        assert_eq!(search_key("ddg", "  Rust   Tauri "), "search:ddg:rust tauri");
+        assert_eq!(page_key(" https://example.com/docs/ "), "page:https://example.com/docs");
+    }
+}
@@ -2,11 +2,12 @@ import { useState, useRef, useEffect, lazy, Suspense } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { Menu, Trash2 } from 'lucide-react';
 import { open } from '@tauri-apps/plugin-dialog';
+import { listen } from '@tauri-apps/api/event';
 
-import { Message, ChatEntry, MiaMode, MiaResponse } from '../types/chat';
+import { Attachment, ClipboardContent, Message, ChatEntry, MiaMode, MiaResponse } from '../types/chat';
 import { ChatSidebar } from './components/Chat/ChatSidebar';
 import { ChatInput } from './components/Chat/ChatInput';
-import { MessageItem } from './components/Chat/MessageItem';
+import { MessageItem, MessageLabels } from './components/Chat/MessageItem';
 import SettingsPage from './components/UI/Settings';
 
 const VRMViewer = lazy(() => import('./components/UI/VRMViewer').then((m) => ({ default: m.VRMViewer })));
@@ -18,17 +19,36 @@ const ChatWindow = () => {
   const [inputText, setInputText] = useState('');
   const [isLoading, setIsLoading] = useState(false);
   const [miaMode, setMiaMode] = useState<MiaMode>('Auto');
-  const [attachedFile, setAttachedFile] = useState<{ name: string; content: string } | null>(null);
+  const [attachedFile, setAttachedFile] = useState<Attachment | null>(null);
   const [isSidebarOpen, setIsSidebarOpen] = useState(false);
   const [showSettings, setShowSettings] = useState(false);
   const [mood, setMood] = useState<'idle' | 'thinking' | 'speaking' | 'scared'>('idle');
   const [chatToDelete, setChatToDelete] = useState<ChatEntry | null>(null);
+  const [labels, setLabels] = useState<MessageLabels & { thinking: string }>({ you: '', page: '{}', thinking: '' });
 
   const messagesEndRef = useRef<HTMLDivElement>(null);
 
   useEffect(() => { fetchChats(true); }, []);
+  useEffect(() => {
+    // Refetched when the settings close, as the UI language may have changed there.
+    if (showSettings) return;
+    Promise.all(['message_you', 'citation_page', 'mia_thinking'].map((key) => invoke<string>('translate', { key })))
+      .then(([you, page, thinking]) => setLabels({ you, page, thinking }))
+      .catch(console.error);
+  }, [showSettings]);
   useEffect(() => { messagesEndRef.current?.scrollIntoView({ behavior: 'smooth' }); }, [messages]);
 
+  useEffect(() => {
+    const unlistenPromise = listen<ClipboardContent>('mia-clipboard-prefill', (event) => {
+      if (event.payload.text) {
+        setInputText(event.payload.text);
+      } else if (event.payload.hasImage) {
+        invoke<Attachment>('attach_clipboard').then(setAttachedFile).catch(console.error);
+      }
+    });
+    return () => { unlistenPromise.then(unlistenFn => unlistenFn()); };
+  }, []);
+
   useEffect(() => {
     if (isLoading) return;
     const interval = setInterval(() => {
@@ -54,14 +74,16 @@ const ChatWindow = () => {
 
   const handleModeChange = async (newMode: MiaMode) => {
     setMiaMode(newMode);
-    try { await invoke('set_mia_mode', { mode: newMode }); } catch (err) { console.error(err); }
+    if (!activeChatId) return;
+    try { await invoke('set_mia_mode', { mode: newMode, chatId: activeChatId }); } catch (err) { console.error(err); }
   };
 
   const handleNewChat = async () => {
     try {
-      const newId: string = await invoke('create_new_chat');
+      const newId: string = await invoke('create_new_chat', { mode: miaMode });
       setActiveChatId(newId);
-      setMessages([{ id: 'welcome', content: "Hi! I'm Mia. How can I assist you today?", sender: 'mia', timestamp: new Date() }]);
+      const greeting = await invoke<string>('translate', { key: 'greeting' });
+      setMessages([{ id: 'welcome', content: greeting, sender: 'mia', timestamp: new Date() }]);
       await fetchChats();
       setIsSidebarOpen(false);
       setMood('idle');
@@ -70,8 +92,9 @@ const ChatWindow = () => {
 
   const handleSwitchChat = async (id: string) => {
     try {
-      await invoke('switch_chat', { chatId: id });
+      const chatMode: MiaMode = await invoke('switch_chat', { chatId: id });
       setActiveChatId(id);
+      setMiaMode(chatMode);
       const history: any[] = await invoke('get_chat_history', { chatId: id });
       setMessages(history.map((m, i) => ({
         id: `${id}-${i}`,
@@ -79,6 +102,7 @@ const ChatWindow = () => {
         sender: m.role === 'user' ? 'user' : 'mia',
         timestamp: m.timestamp ? new Date(m.timestamp) : new Date(),
         sources: m.sources,
+        citations: m.citations,
       })));
       setIsSidebarOpen(false);
       setMood('idle');
@@ -112,37 +136,57 @@ const ChatWindow = () => {
 
   const handleAttachFile = async () => {
     try {
-      const selected = await open({ multiple: false, filters: [{ name: 'Dokumentumok', extensions: ['pdf', 'docx', 'txt'] }] });
+      const selected = await open({ multiple: false, filters: [
+        { name: 'Dokumentumok', extensions: ['pdf', 'docx', 'odt', 'rtf', 'epub', 'xlsx', 'xls', 'ods', 'pptx', 'odp', 'txt', 'md', 'csv'] },
+        { name: 'Képek', extensions: ['png', 'jpg', 'jpeg'] },
+        { name: 'Minden fájl', extensions: ['*'] },
+      ] });
       if (selected && typeof selected === 'string') {
-        const fileContent: string = await invoke('upload_file', { path: selected });
-        setAttachedFile({ name: selected.split('\\').pop() || 'fájl', content: fileContent });
+        const attachment: Attachment = await invoke('upload_file', { path: selected });
+        setAttachedFile(attachment);
       }
     } catch (err) { console.error(err); }
   };
 
+  const handleAttachClipboard = async () => {
+    try {
+      const attachment: Attachment = await invoke('attach_clipboard');
+      setAttachedFile(attachment);
+    } catch (err) { console.error(err); }
+  };
+
   const handleSend = async () => {
     if ((!inputText.trim() && !attachedFile) || isLoading) return;
-    const fullPrompt = attachedFile ? `${attachedFile.content}\n\nKérdés: ${inputText}` : inputText;
+    const isFirstExchange = !messages.some(m => m.sender === 'user');
 
-    setMessages(prev => [...prev, { id: Date.now(), content: attachedFile ? `📄 ${attachedFile.name}\n${inputText}` : inputText, sender: 'user', timestamp: new Date() }]);
+    setMessages(prev => [...prev, { id: Date.now(), content: attachedFile ? `${attachedFile.mime.startsWith('image/') ? '🖼️' : '📄'} ${attachedFile.name}\n${inputText}` : inputText, sender: 'user', timestamp: new Date() }]);
     setInputText('');
     setAttachedFile(null);
     setIsLoading(true);
     setMood('thinking');
 
     try {
-      const response: MiaResponse = await invoke('ask_mia', { message: fullPrompt });
+      const response: MiaResponse = await invoke('ask_mia', {
+        message: inputText,
+        chatId: activeChatId || null,
+        attachmentIds: attachedFile ? [attachedFile.id] : null,
+      });
 
       const scaredKeywords = ['ijesztő', 'félelmetes', 'halál', 'veszély', 'szörnyű', 'rettenetes'];
       const isScary = scaredKeywords.some(kw => response.content.toLowerCase().includes(kw));
 
       setMood(isScary ? 'scared' : 'speaking');
 
-      setMessages(prev => [...prev, { id: Date.now() + 1, content: response.content, sender: 'mia', timestamp: new Date(), tokens: response.tokens, speed: response.speed, sources: response.sources }]);
+      setMessages(prev => [...prev, { id: Date.now() + 1, content: response.content, sender: 'mia', timestamp: new Date(), tokens: response.tokens, speed: response.speed, acceptanceRate: response.acceptanceRate, sources: response.sources, citations: response.citations }]);
 
       setTimeout(() => setMood('idle'), 2000);
 
       await fetchChats();
+      if (isFirstExchange) {
+        invoke('generate_chat_title', { chatId: activeChatId || null })
+          .then(() => fetchChats())
+          .catch(err => console.error(err));
+      }
     } catch (err) {
       console.error(err);
       setMood('idle');
@@ -186,10 +230,10 @@ const ChatWindow = () => {
 
             <div className="flex-1 overflow-y-auto px-3 py-4 space-y-4 custom-scrollbar">
               {messages.map((msg) => (
-                <MessageItem key={msg.id} message={msg} />
+                <MessageItem key={msg.id} message={msg} labels={labels} />
               ))}
               {isLoading && (
-                <div className="text-xs text-slate-500 animate-pulse px-4">Mia gondolkodik...</div>
+                <div className="text-xs text-slate-500 animate-pulse px-4">{labels.thinking}</div>
               )}
               <div ref={messagesEndRef} />
             </div>
@@ -203,6 +247,7 @@ const ChatWindow = () => {
               attachedFile={attachedFile}
               setAttachedFile={setAttachedFile}
               onAttach={handleAttachFile}
+              onAttachClipboard={handleAttachClipboard}
               onSend={handleSend}
             />
           </>
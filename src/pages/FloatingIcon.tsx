@@ -4,18 +4,34 @@ import { useState, useEffect } from 'react';
 import "../index.css";
 import { useModelStore } from '../store/modelStore';
 import { listen } from '@tauri-apps/api/event';
+import { LoadingProgress, SystemStats } from '../types/chat';
 
 const FloatingIcon = () => {
   const [isHovered, setIsHovered] = useState(false);
   const [isPressed, setIsPressed] = useState(false);
   const [isPulsing, setIsPulsing] = useState(false);
   const isLoading = useModelStore((s) => s.isLoading);
+  const progress = useModelStore((s) => s.progress);
+  const error = useModelStore((s) => s.error);
 
   useEffect(() => {
     const unlistenPromise = listen('mia-loading-status', (event) => {
       console.log('[FloatingIcon] Esemény érkezett:', event.payload);
       useModelStore.getState().setLoading(!!event.payload);
     });
+    const unlistenProgress = listen<LoadingProgress>('mia-loading-progress', (event) => {
+      useModelStore.getState().setProgress(event.payload.percent);
+    });
+    const unlistenError = listen<string>('mia-loading-error', (event) => {
+      console.error('[FloatingIcon] Betöltési hiba:', event.payload);
+      useModelStore.getState().setError(event.payload);
+    });
+    // Without a llama.cpp backend no model can ever load; show it right away.
+    invoke<SystemStats>('get_system_stats').then((stats) => {
+      if (stats.backend.kind === 'none') {
+        useModelStore.getState().setError(stats.backend.error ?? null);
+      }
+    }).catch((error) => console.error('[FloatingIcon] Rendszer állapot hiba:', error));
 
     const interval = setInterval(() => {
       setIsPulsing(true);
@@ -24,7 +40,9 @@ const FloatingIcon = () => {
 
     return () => {
       clearInterval(interval);
-      unlistenPromise.then(unlistenFn => unlistenFn())
+      unlistenPromise.then(unlistenFn => unlistenFn());
+      unlistenProgress.then(unlistenFn => unlistenFn());
+      unlistenError.then(unlistenFn => unlistenFn());
     };
   }, []);
 
@@ -44,6 +62,7 @@ const FloatingIcon = () => {
       <div
         data-tauri-drag-region
         className="relative w-[70px] h-[70px] cursor-pointer select-none"
+        title={error ?? (isLoading ? `${progress}%` : undefined)}
         onClick={handleClick}
         onMouseEnter={() => setIsHovered(true)}
         onMouseLeave={() => setIsHovered(false)}
@@ -95,9 +114,9 @@ const FloatingIcon = () => {
 
         </div>
 
-        <div className={`absolute -bottom-0.5 -right-0.5 w-4 h-4 rounded-full border-2 border-slate-950 shadow-lg transition-all duration-500 ${isLoading ? 'bg-orange-500 shadow-orange-500/50' : 'bg-green-500 shadow-green-500/50'
+        <div className={`absolute -bottom-0.5 -right-0.5 w-4 h-4 rounded-full border-2 border-slate-950 shadow-lg transition-all duration-500 ${error ? 'bg-red-500 shadow-red-500/50' : isLoading ? 'bg-orange-500 shadow-orange-500/50' : 'bg-green-500 shadow-green-500/50'
           }`}>
-          <div className={`absolute inset-0 rounded-full animate-ping ${isLoading ? 'bg-orange-400/50' : 'bg-green-400/50'
+          <div className={`absolute inset-0 rounded-full animate-ping ${error ? 'bg-red-400/50' : isLoading ? 'bg-orange-400/50' : 'bg-green-400/50'
             }`} />
         </div>
       </div>
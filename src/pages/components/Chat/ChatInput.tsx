@@ -1,6 +1,7 @@
-import React, { useRef, useEffect } from 'react';
-import { Send, Loader2, Paperclip, X, FileText, Brain, ShieldCheck, Sparkles, Globe } from 'lucide-react';
-import { MiaMode } from '../../../types/chat';
+import React, { useRef, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Send, Loader2, Paperclip, ClipboardPaste, X, FileText, Brain, ShieldCheck, Sparkles, Globe } from 'lucide-react';
+import { Attachment, MiaMode } from '../../../types/chat';
 
 interface ChatInputProps {
   inputText: string;
@@ -8,9 +9,10 @@ interface ChatInputProps {
   isLoading: boolean;
   miaMode: MiaMode;
   onModeChange: (mode: MiaMode) => void;
-  attachedFile: { name: string; content: string } | null;
+  attachedFile: Attachment | null;
   setAttachedFile: (file: any) => void;
   onAttach: () => void;
+  onAttachClipboard: () => void;
   onSend: () => void;
 }
 
@@ -22,9 +24,14 @@ const MODES = [
 ];
 
 export const ChatInput: React.FC<ChatInputProps> = ({
-  inputText, setInputText, isLoading, miaMode, onModeChange, attachedFile, setAttachedFile, onAttach, onSend
+  inputText, setInputText, isLoading, miaMode, onModeChange, attachedFile, setAttachedFile, onAttach, onAttachClipboard, onSend
 }) => {
   const textareaRef = useRef<HTMLTextAreaElement>(null);
+  const [clipboardTooltip, setClipboardTooltip] = useState('');
+
+  useEffect(() => {
+    invoke<string>('translate', { key: 'clipboard_attach_tooltip' }).then(setClipboardTooltip).catch(() => {});
+  }, []);
 
   useEffect(() => {
     if (textareaRef.current) {
@@ -60,6 +67,7 @@ export const ChatInput: React.FC<ChatInputProps> = ({
 
       <div className="flex items-end space-x-2 bg-slate-800/60 border border-white/8 rounded-2xl px-3 py-2">
         <button onClick={onAttach} className="p-1.5 text-slate-500 hover:text-slate-300 mb-0.5"><Paperclip className="w-4 h-4" /></button>
+        <button onClick={onAttachClipboard} title={clipboardTooltip} className="p-1.5 text-slate-500 hover:text-slate-300 mb-0.5"><ClipboardPaste className="w-4 h-4" /></button>
         <textarea
           ref={textareaRef}
           value={inputText}
@@ -1,14 +1,21 @@
 import React from 'react';
-import { Zap, Hash, Globe, ExternalLink } from 'lucide-react';
+import { Zap, Hash, Globe, ExternalLink, FileText, Target } from 'lucide-react';
 import { Message } from '../../../types/chat';
 import MarkdownResponse from '../UI/MarkdownResponse';
 import { openUrl } from '@tauri-apps/plugin-opener';
 
+/** UI strings from the locale catalogs; `page` has a `{}` for the page number. */
+export interface MessageLabels {
+  you: string;
+  page: string;
+}
+
 interface MessageItemProps {
   message: Message;
+  labels: MessageLabels;
 }
 
-export const MessageItem: React.FC<MessageItemProps> = ({ message }) => {
+export const MessageItem: React.FC<MessageItemProps> = ({ message, labels }) => {
   const isUser = message.sender === 'user';
 
   return (
@@ -16,7 +23,7 @@ export const MessageItem: React.FC<MessageItemProps> = ({ message }) => {
       <div className={`flex flex-col max-w-[85%] md:max-w-[75%] ${isUser ? 'items-end' : 'items-start'}`}>
         <div className="flex items-center space-x-2 mb-1 px-1">
           <span className="text-[10px] text-slate-500 font-medium">
-            {isUser ? 'Te' : 'Mia'}
+            {isUser ? labels.you : 'Mia'}
           </span>
           <span className="text-[10px] text-slate-600">
             {message.timestamp.toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}
@@ -55,6 +62,22 @@ export const MessageItem: React.FC<MessageItemProps> = ({ message }) => {
           </div>
         )}
 
+        {!isUser && message.citations && message.citations.length > 0 && (
+          <div className="mt-2 flex flex-wrap gap-1.5">
+            {message.citations.map((c, cIdx) => (
+              <span
+                key={cIdx}
+                className="flex items-center space-x-1.5 bg-slate-800/60 border border-slate-700/50 px-2.5 py-1 rounded-lg text-xs text-slate-400"
+              >
+                <FileText className="w-3 h-3 flex-shrink-0 text-cyan-400" />
+                <span className="truncate max-w-[180px]">
+                  {c.document}{c.page ? `, ${labels.page.replace('{}', String(c.page))}` : c.section ? ` – ${c.section}` : ''}
+                </span>
+              </span>
+            ))}
+          </div>
+        )}
+
         {!isUser && message.speed !== undefined && (
           <div className="flex items-center space-x-3 mt-1.5 px-1">
             <span className="text-[10px] text-slate-600 flex items-center space-x-1">
@@ -65,6 +88,12 @@ export const MessageItem: React.FC<MessageItemProps> = ({ message }) => {
               <Hash className="w-2.5 h-2.5" />
               <span>{message.tokens} token</span>
             </span>
+            {message.acceptanceRate != null && (
+              <span className="text-[10px] text-slate-600 flex items-center space-x-1" title="Draft">
+                <Target className="w-2.5 h-2.5" />
+                <span>{Math.round(message.acceptanceRate * 100)}%</span>
+              </span>
+            )}
           </div>
         )}
       </div>
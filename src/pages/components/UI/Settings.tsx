@@ -1,6 +1,30 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Gamepad2, Globe, Clock, Save, RefreshCw, Plus, X as CloseIcon, Monitor, Palette, ArrowLeft } from 'lucide-react';
+import { Gamepad2, Globe, Clock, Save, RefreshCw, Plus, X as CloseIcon, Monitor, Palette, ArrowLeft, Tags } from 'lucide-react';
+import { Persona } from '../../../types/chat';
+
+/** Keyword lists offered for every persona; 'any' applies to every language. */
+const KEYWORD_LANGUAGES = ['en', 'hu', 'any'];
+
+const parseKeywords = (text: string) => text.split(',').map((k) => k.trim()).filter(Boolean);
+
+/** Editable text of each keyword list, one comma-separated line per language. */
+const keywordDrafts = (persona: Persona) => {
+  const languages = [...new Set([...KEYWORD_LANGUAGES, ...Object.keys(persona.triggerKeywords)])];
+  return Object.fromEntries(languages.map((lang) => [lang, (persona.triggerKeywords[lang] ?? []).join(', ')]));
+};
+
+/** Turns the drafts back into lists, leaving out the empty languages. */
+const draftsToKeywords = (drafts: Record<string, string>) => Object.fromEntries(
+  Object.entries(drafts)
+    .map(([lang, text]) => [lang, parseKeywords(text)] as const)
+    .filter(([, list]) => list.length > 0)
+);
+
+const sameKeywords = (a: Record<string, string[]>, b: Record<string, string[]>) => {
+  const keys = Object.keys(a);
+  return keys.length === Object.keys(b).length && keys.every((lang) => a[lang].join('\n') === b[lang]?.join('\n'));
+};
 
 interface SettingsPageProps {
   onBack?: () => void;
@@ -16,9 +40,14 @@ const SettingsPage = ({ onBack }: SettingsPageProps) => {
     theme: 'dark',
   });
 
+  const [storedSettings, setStoredSettings] = useState<Record<string, unknown>>({});
   const [newGame, setNewGame] = useState('');
   const [isSaving, setIsSaving] = useState(false);
   const [isFullscreen, setIsFullscreen] = useState(false);
+  const [personas, setPersonas] = useState<Persona[]>([]);
+  const [keywords, setKeywords] = useState<Record<string, Record<string, string>>>({});
+  const [keywordPersonaId, setKeywordPersonaId] = useState('');
+  const [keywordLanguage, setKeywordLanguage] = useState(KEYWORD_LANGUAGES[0]);
 
   useEffect(() => {
     const checkFullscreen = () => {
@@ -36,6 +65,7 @@ const SettingsPage = ({ onBack }: SettingsPageProps) => {
       try {
         const savedData = await invoke('get_settings') as any;
         if (savedData) {
+          setStoredSettings(savedData);
           setSettings({
             searxngUrl: savedData.searxngUrl,
             launchOnStartup: savedData.launchOnStartup,
@@ -45,6 +75,10 @@ const SettingsPage = ({ onBack }: SettingsPageProps) => {
             theme: savedData.theme || 'dark',
           });
         }
+        const loadedPersonas = await invoke<Persona[]>('get_personas');
+        setPersonas(loadedPersonas);
+        setKeywords(Object.fromEntries(loadedPersonas.map((p) => [p.id, keywordDrafts(p)])));
+        setKeywordPersonaId(loadedPersonas.find((p) => p.id !== 'basic')?.id ?? loadedPersonas[0]?.id ?? '');
       } catch (error) {
         console.error('Nem sikerült betölteni a beállításokat:', error);
       }
@@ -76,6 +110,7 @@ const SettingsPage = ({ onBack }: SettingsPageProps) => {
 
     const payload = {
       settings: {
+        ...storedSettings,
         games: settings.games,
         searxngUrl: settings.searxngUrl,
         launchOnStartup: settings.launchOnStartup,
@@ -87,6 +122,13 @@ const SettingsPage = ({ onBack }: SettingsPageProps) => {
 
     try {
       await invoke('save_settings', payload);
+      const saved = await Promise.all(personas.map((persona) => {
+        const triggerKeywords = draftsToKeywords(keywords[persona.id] ?? {});
+        return sameKeywords(triggerKeywords, persona.triggerKeywords)
+          ? persona
+          : invoke<Persona>('save_persona', { persona: { ...persona, triggerKeywords } });
+      }));
+      setPersonas(saved);
       await new Promise(resolve => setTimeout(resolve, 600));
     } catch (error) {
       console.error('Mentési hiba:', error);
@@ -108,6 +150,9 @@ const SettingsPage = ({ onBack }: SettingsPageProps) => {
     }
   };
 
+  const keywordPersona = personas.find((p) => p.id === keywordPersonaId);
+  const keywordLanguages = Object.keys(keywords[keywordPersonaId] ?? {});
+
   const gridCols = isFullscreen ? 'lg:grid-cols-2' : 'lg:grid-cols-1';
 
   return (
@@ -324,6 +369,63 @@ const SettingsPage = ({ onBack }: SettingsPageProps) => {
         </div>
       )}
 
+      {keywordPersona && (
+        <div className="mt-4 sm:mt-6 max-w-7xl mx-auto">
+          <div className="p-4 sm:p-6 rounded-2xl sm:rounded-3xl bg-slate-900/60 backdrop-blur-xl border border-slate-700/50 shadow-xl">
+            <div className="flex items-center space-x-3 mb-4 sm:mb-6">
+              <div className="p-2 rounded-xl bg-violet-500/20 text-violet-400 flex-shrink-0">
+                <Tags className="w-4 h-4 sm:w-5 sm:h-5" />
+              </div>
+              <div className="min-w-0">
+                <h2 className="text-lg sm:text-xl font-semibold text-slate-200">Auto Mode Keywords</h2>
+                <p className="text-xs text-slate-400 font-normal">Words that make Auto mode pick a persona, per language</p>
+              </div>
+            </div>
+
+            <div className="space-y-4">
+              <div className="flex flex-wrap gap-2">
+                <select
+                  value={keywordPersonaId}
+                  onChange={(e) => setKeywordPersonaId(e.target.value)}
+                  className="p-2 rounded-lg bg-slate-800 border border-slate-700 text-slate-100 text-sm focus:outline-none focus:border-slate-600"
+                >
+                  {personas.map((p) => (
+                    <option key={p.id} value={p.id}>{p.icon} {p.name}</option>
+                  ))}
+                </select>
+                <div className="flex rounded-lg bg-slate-800 border border-slate-700 p-0.5">
+                  {keywordLanguages.map((lang) => (
+                    <button
+                      key={lang}
+                      onClick={() => setKeywordLanguage(lang)}
+                      className={`px-3 py-1 rounded-md text-xs font-bold uppercase tracking-widest transition-colors ${
+                        lang === keywordLanguage ? 'bg-violet-600 text-white' : 'text-slate-400 hover:text-slate-200'
+                      }`}
+                    >
+                      {lang}
+                    </button>
+                  ))}
+                </div>
+              </div>
+
+              <textarea
+                value={keywords[keywordPersonaId]?.[keywordLanguage] ?? ''}
+                onChange={(e) => setKeywords({
+                  ...keywords,
+                  [keywordPersonaId]: { ...keywords[keywordPersonaId], [keywordLanguage]: e.target.value },
+                })}
+                rows={3}
+                className="w-full p-3 rounded-xl bg-slate-800/60 border border-slate-700/50 text-slate-100 placeholder:text-slate-500 focus:outline-none focus:border-violet-500/50 text-sm transition-all shadow-inner resize-none"
+                placeholder="Comma-separated, e.g. news, weather, who won"
+              />
+              <p className="text-xs text-slate-500">
+                Keywords match whole words; end one with * to match word beginnings too (e.g. élet* for életről).
+              </p>
+            </div>
+          </div>
+        </div>
+      )}
+
       <div className="mt-6 sm:mt-8 pt-4 sm:pt-6 border-t border-white/5">
         <div className={`flex flex-col ${isFullscreen ? 'sm:flex-row' : ''} justify-end space-y-3 ${isFullscreen ? 'sm:space-y-0 sm:space-x-3' : ''}`}>
           <button
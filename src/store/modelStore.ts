@@ -2,10 +2,18 @@ import { create } from 'zustand';
 
 interface ModelStore {
   isLoading: boolean;
+  progress: number;
+  error: string | null;
   setLoading: (loading: boolean) => void;
+  setProgress: (progress: number) => void;
+  setError: (error: string | null) => void;
 }
 
 export const useModelStore = create<ModelStore>((set) => ({
   isLoading: false,
-  setLoading: (loading) => set({ isLoading: loading }),
-}));
\ No newline at end of file
+  progress: 0,
+  error: null,
+  setLoading: (loading) => set(loading ? { isLoading: true, error: null } : { isLoading: false }),
+  setProgress: (progress) => set({ progress }),
+  setError: (error) => set({ error }),
+}));
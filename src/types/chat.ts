@@ -1,15 +1,61 @@
-export type MiaMode = 'Auto' | 'Basic' | 'Philosophy' | 'Search';
+export type MiaMode = 'Auto' | 'Basic' | 'Philosophy' | 'Search' | { Persona: string };
+
+export interface SamplingParams {
+  temperature: number;
+  topK: number;
+  topP: number;
+  minP: number;
+  typicalP: number;
+  repeatPenalty: number;
+  frequencyPenalty: number;
+  presencePenalty: number;
+  penaltyLastN: number;
+  mirostat: 'Off' | 'V1' | 'V2';
+  mirostatTau: number;
+  mirostatEta: number;
+  dryMultiplier: number;
+  dryBase: number;
+  dryAllowedLength: number;
+  /** Recent tokens DRY looks at; -1 is the whole context. */
+  dryLastN: number;
+  /** Fixed seed for reproducible answers; random when empty. */
+  seed?: number | null;
+}
+
+export interface Persona {
+  id: string;
+  name: string;
+  icon: string;
+  systemPrompt: string;
+  sampling: SamplingParams;
+  webSearch: boolean;
+  /** Auto-mode keywords per language code ('en', 'hu'); 'any' applies to every language. A trailing `*` matches word prefixes. */
+  triggerKeywords: Record<string, string[]>;
+  builtIn: boolean;
+  /** Model file in `models/`; the default model when empty. */
+  model?: string | null;
+}
 
 export interface WebSource {
   title: string;
   url: string;
 }
 
+export interface Citation {
+  document: string;
+  page?: number | null;
+  section?: string | null;
+}
+
 export interface MiaResponse {
   content: string;
   tokens: number;
   speed: number;
   sources: WebSource[];
+  citations: Citation[];
+  mode: MiaMode;
+  /** Share of draft tokens kept; only with speculative decoding. */
+  acceptanceRate?: number | null;
 }
 
 export interface Message {
@@ -19,12 +65,114 @@ export interface Message {
   timestamp: Date;
   tokens?: number;
   speed?: number;
+  acceptanceRate?: number | null;
   isSearch?: boolean;
   sources?: WebSource[];
+  citations?: Citation[];
 }
 
 export interface ChatEntry {
   id: string;
   name: string;
   last_active: number;
-}
\ No newline at end of file
+  /** The chat's own model (`set_chat_model`); the persona's model applies when missing. */
+  model?: string | null;
+}
+
+export interface Attachment {
+  id: string;
+  name: string;
+  mime: string;
+  hash: string;
+  size: number;
+  chars: number;
+  pages?: number | null;
+  truncated?: boolean;
+  created_at: number;
+}
+
+export interface DocumentAnswer {
+  content: string;
+  citations: Citation[];
+}
+
+export interface DocumentProgress {
+  attachmentId: string;
+  stage: 'map' | 'reduce' | 'retrieve' | 'generate' | 'done';
+  done: number;
+  total: number;
+}
+
+export interface KnowledgeBaseInfo {
+  id: string;
+  name: string;
+  root: string;
+  fileCount: number;
+  updatedAt: number;
+}
+
+export interface KnowledgeProgress {
+  knowledgeBaseId: string;
+  done: number;
+  total: number;
+}
+
+export interface ClipboardContent {
+  text?: string | null;
+  hasImage: boolean;
+}
+
+/** Share of the model file llama.cpp has loaded, reported by its progress callback. */
+export interface LoadingProgress {
+  percent: number;
+}
+
+/** What inference runs on; `kind` is 'none' when llama.cpp could not be initialized. */
+export interface ActiveBackend {
+  kind: 'gpu' | 'cpu' | 'none';
+  device?: string | null;
+  gpuLayers: number;
+  threads: number;
+  error?: string | null;
+}
+
+/** Result of `get_system_stats`. */
+export interface SystemStats {
+  cpu: { percentage: number; cores: number[] };
+  memory: { percentage: number; display: string };
+  backend: ActiveBackend;
+}
+
+/** Result of `get_model_info`; `null` while no model is loaded. */
+export interface ModelInfo {
+  file: string;
+  architecture?: string | null;
+  parameterCount: number;
+  quantization?: string | null;
+  contextLength: number;
+  vocabSize: number;
+  hasChatTemplate: boolean;
+  hasVision: boolean;
+  loadSeconds: number;
+  gpuLayers: number;
+}
+
+export interface DownloadProgress {
+  file: string;
+  stage: 'download' | 'verify' | 'done';
+  downloaded: number;
+  total?: number | null;
+}
+
+export interface DownloadedModel {
+  name: string;
+  sha256: string;
+  size: number;
+  verified: boolean;
+}
+
+/** Result of `generate_structured`; `json` is set when a JSON schema was given. */
+export interface StructuredOutput {
+  text: string;
+  json?: unknown;
+}